@@ -0,0 +1,40 @@
+#![cfg_attr(not(test), no_main)]
+#[cfg(not(test))]
+sp1_zkvm::entrypoint!(main);
+
+use alloy_sol_types::SolType;
+use sha2::{Digest, Sha256};
+use weighted_raffle_program::shard::{
+    aggregate_shards, shard_vkey_bytes, AggregationProgramInput, AggregationPublicValuesTuple,
+    ShardSummary,
+};
+
+pub fn main() {
+    let input = sp1_zkvm::io::read::<AggregationProgramInput>();
+
+    // Verify the shard proofs, which the host provides in the same order as their public values.
+    let shards: Vec<ShardSummary> = input
+        .shards
+        .iter()
+        .map(|public_values| {
+            let public_values_digest: [u8; 32] = Sha256::digest(public_values).into();
+            sp1_zkvm::lib::verify::verify_sp1_proof(&input.shard_vkey, &public_values_digest);
+            ShardSummary::abi_decode(public_values)
+        })
+        .collect();
+
+    let registration = aggregate_shards(&shards, input.commitment_tree);
+
+    // Encode the public values of the program.
+    let bytes = AggregationPublicValuesTuple::abi_encode(&(
+        registration.commitment_root,
+        registration.total_weight,
+        registration.entry_count,
+        input.commitment_tree.id(),
+        input.commitment_tree.encoding().id(),
+        shard_vkey_bytes(&input.shard_vkey),
+        shards.len() as u64,
+    ));
+    // Commit to the public values of the program.
+    sp1_zkvm::io::commit_slice(&bytes);
+}
@@ -0,0 +1,23 @@
+#![cfg_attr(not(test), no_main)]
+#[cfg(not(test))]
+sp1_zkvm::entrypoint!(main);
+
+use weighted_raffle_program::{
+    bench::{get_materialized_commitment_root, BenchProgramInput},
+    merkle::get_commitment_root,
+};
+
+pub fn main() {
+    let input = sp1_zkvm::io::read::<BenchProgramInput>();
+
+    println!("cycle-tracker-start: commitment-root");
+    let commit_root = if input.materialized {
+        get_materialized_commitment_root(&input.entries, input.commitment_tree)
+    } else {
+        get_commitment_root(&input.entries, input.commitment_tree)
+    };
+    println!("cycle-tracker-end: commitment-root");
+
+    // Commit to the public values of the program.
+    sp1_zkvm::io::commit_slice(&commit_root);
+}
@@ -0,0 +1,64 @@
+// Commitment root built as before streaming, for the bench guest in `bench-program` to compare the
+// cycles of both builders. Only compiled with the `bench` feature, so the raffle program never
+// materializes the commitment tree
+use crate::{
+    merkle::{
+        get_commitment_leaves, get_merkle_root, incremental, poseidon, tagged, Sha256Algorithm,
+        TreeKind,
+    },
+    raffle::Entry,
+};
+use rs_merkle::MerkleTree;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BenchProgramInput {
+    pub entries: Vec<Entry>,
+    pub commitment_tree: TreeKind,
+    // Compute the root with `get_materialized_commitment_root` instead of `get_commitment_root`
+    pub materialized: bool,
+}
+
+// Same root as `get_commitment_root`: collect every leaf, then build the full tree
+pub fn get_materialized_commitment_root(entries: &[Entry], kind: TreeKind) -> [u8; 32] {
+    let leaves = get_commitment_leaves(entries, kind);
+    match kind {
+        TreeKind::Positional(_) => get_merkle_root(leaves),
+        TreeKind::Tagged(_) => tagged::root(&leaves),
+        TreeKind::Incremental(_) => incremental::root(&leaves),
+        TreeKind::Poseidon => poseidon::root(&leaves),
+        TreeKind::Sha256(_) => MerkleTree::<Sha256Algorithm>::from_leaves(&leaves)
+            .root()
+            .unwrap(),
+        // Never streamed
+        TreeKind::OpenZeppelin | TreeKind::Sparse(_) => kind
+            .root(&leaves)
+            .expect("sparse trees can only commit the winners, not the entries"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{merkle::get_commitment_root, merkle::leaf::LeafEncoding, test_util::entries};
+
+    #[test]
+    fn test_materialized_commitment_root() {
+        for kind in [
+            TreeKind::Positional(LeafEncoding::Packed),
+            TreeKind::Tagged(LeafEncoding::Abi),
+            TreeKind::Incremental(LeafEncoding::Packed),
+            TreeKind::OpenZeppelin,
+            TreeKind::Poseidon,
+            TreeKind::Sha256(LeafEncoding::Packed),
+        ] {
+            for n in [2, 3, 7, 8, 33] {
+                let entries = entries(&vec![10; n]);
+                assert_eq!(
+                    get_materialized_commitment_root(&entries, kind),
+                    get_commitment_root(&entries, kind)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+// Fingerprint of exactly what went into the program: keccak256 of a canonical encoding of its
+// input, committed in the public values so that audits can match a proof to an input file
+// The encoding packs every field in declaration order, integers as big-endian bytes of their own
+// width, with a uint64 length before every list and a flag byte (0 or 1) before every optional
+// value. Trees and policies are encoded by the ids committed in the public values.
+use crate::{
+    merkle::TreeKind,
+    raffle::{
+        sublinear::{DrawHit, SublinearDraw},
+        weight::{Balance, WeightPolicy},
+        Entry, Quota,
+    },
+    WeightedRaffleProgramInput,
+};
+use sha3::{Digest, Keccak256};
+
+// Where the canonical encoding is written: straight into the hasher in the program, which never
+// holds a copy of the input, or into a buffer on the host and in tests
+trait Sink {
+    fn put(&mut self, bytes: &[u8]);
+}
+
+impl Sink for Vec<u8> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+impl Sink for Keccak256 {
+    fn put(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+}
+
+fn push_group(out: &mut impl Sink, group: Option<u32>) {
+    match group {
+        None => out.put(&[0]),
+        Some(group) => {
+            out.put(&[1]);
+            out.put(&group.to_be_bytes());
+        }
+    }
+}
+
+fn push_policy(out: &mut impl Sink, policy: WeightPolicy) {
+    out.put(&[policy.id()]);
+    out.put(&policy.param().to_be_bytes());
+}
+
+fn push_tree(out: &mut impl Sink, kind: TreeKind) {
+    out.put(&[kind.id(), kind.encoding().id()]);
+}
+
+fn push_balance(out: &mut impl Sink, balance: &Balance) {
+    out.put(&balance.address);
+    out.put(&balance.balance.to_be_bytes());
+    push_group(out, balance.group);
+}
+
+fn push_quota(out: &mut impl Sink, quota: &Quota) {
+    out.put(&quota.group.to_be_bytes());
+    out.put(&quota.num_winners.to_be_bytes());
+}
+
+fn push_entry(out: &mut impl Sink, entry: &Entry) {
+    out.put(&entry.address);
+    out.put(&entry.start.to_be_bytes());
+    out.put(&entry.end.to_be_bytes());
+    push_group(out, entry.group);
+}
+
+// Proven hits are tagged 0 and repeat hits 1
+fn push_hit(out: &mut impl Sink, hit: &DrawHit) {
+    match hit {
+        DrawHit::Proven {
+            entry,
+            index,
+            proof,
+        } => {
+            out.put(&[0]);
+            push_entry(out, entry);
+            out.put(&index.to_be_bytes());
+            out.put(&(proof.len() as u64).to_be_bytes());
+            for node in proof {
+                out.put(node);
+            }
+        }
+        DrawHit::Repeat(index) => {
+            out.put(&[1]);
+            out.put(&index.to_be_bytes());
+        }
+    }
+}
+
+fn push_sublinear(out: &mut impl Sink, sublinear: Option<&SublinearDraw>) {
+    let Some(draw) = sublinear else {
+        out.put(&[0]);
+        return;
+    };
+    out.put(&[1]);
+    out.put(&draw.commitment_root);
+    out.put(&draw.total_weight.to_be_bytes());
+    out.put(&draw.entry_count.to_be_bytes());
+    out.put(&(draw.hits.len() as u64).to_be_bytes());
+    for hit in &draw.hits {
+        push_hit(out, hit);
+    }
+}
+
+fn push_input(out: &mut impl Sink, input: &WeightedRaffleProgramInput) {
+    out.put(&input.seed);
+    out.put(&(input.balances.len() as u64).to_be_bytes());
+    for balance in &input.balances {
+        push_balance(out, balance);
+    }
+    push_policy(out, input.weight_policy);
+    out.put(&[input.mode.id()]);
+    out.put(&input.num_winners.to_be_bytes());
+    out.put(&(input.quotas.len() as u64).to_be_bytes());
+    for quota in &input.quotas {
+        push_quota(out, quota);
+    }
+    push_tree(out, input.commitment_tree);
+    push_tree(out, input.winners_tree);
+    push_sublinear(out, input.sublinear.as_ref());
+    out.put(&input.raffle_id.to_be_bytes());
+    out.put(&input.chain_id.to_be_bytes());
+    out.put(&input.raffle_contract);
+    match input.inline_winners {
+        None => out.put(&[0]),
+        Some(limit) => {
+            out.put(&[1]);
+            out.put(&limit.to_be_bytes());
+        }
+    }
+}
+
+// The bytes `input_hash` hashes, for the host and tests (the program streams them into the hasher)
+pub fn canonical_encoding(input: &WeightedRaffleProgramInput) -> Vec<u8> {
+    let mut out = vec![];
+    push_input(&mut out, input);
+    out
+}
+
+pub fn input_hash(input: &WeightedRaffleProgramInput) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    push_input(&mut hasher, input);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        merkle::{leaf::LeafEncoding, proof::get_sublinear_draw},
+        raffle::weight::derive_entries,
+        RaffleMode,
+    };
+
+    fn input() -> WeightedRaffleProgramInput {
+        WeightedRaffleProgramInput {
+            seed: [7; 32],
+            balances: (1..=4)
+                .map(|i| Balance {
+                    address: [i; 20],
+                    balance: i as u64 * 10,
+                    group: (i % 2 == 0).then_some(1),
+                })
+                .collect(),
+            weight_policy: WeightPolicy::Linear,
+            mode: RaffleMode::Winners,
+            num_winners: 2,
+            quotas: vec![Quota {
+                group: 1,
+                num_winners: 1,
+            }],
+            commitment_tree: TreeKind::Positional(LeafEncoding::Packed),
+            winners_tree: TreeKind::Positional(LeafEncoding::Packed),
+            sublinear: None,
+            raffle_id: 1,
+            chain_id: 1,
+            raffle_contract: [9; 20],
+            inline_winners: None,
+        }
+    }
+
+    #[test]
+    fn test_canonical_encoding() {
+        let input = input();
+        let encoding = canonical_encoding(&input);
+        // seed, 4 balances (with 2 groups), policy, mode, num_winners, 1 quota, trees, no sublinear
+        // draw, raffle binding, no inline limit
+        let len = 32 + 8 + 4 * 29 + 2 * 4 + 9 + 1 + 8 + 8 + 12 + 4 + 1 + 8 + 8 + 20 + 1;
+        assert_eq!(encoding.len(), len);
+        assert_eq!(&encoding[..32], &[7; 32]);
+        assert_eq!(&encoding[len - 21..len - 1], &[9; 20]);
+    }
+
+    #[test]
+    fn test_input_hash_streams_canonical_encoding() {
+        let mut input = input();
+        input.sublinear = Some(get_sublinear_draw(
+            2,
+            input.seed,
+            &derive_entries(&input.balances, input.weight_policy),
+            input.commitment_tree,
+        ));
+        let expected: [u8; 32] = Keccak256::digest(canonical_encoding(&input)).into();
+        assert_eq!(input_hash(&input), expected);
+    }
+
+    #[test]
+    fn test_input_hash_covers_every_field() {
+        let hash = input_hash(&input());
+        assert_eq!(hash, input_hash(&input()));
+
+        let variants: Vec<fn(&mut WeightedRaffleProgramInput)> = vec![
+            |input| input.seed[0] = 0,
+            |input| input.balances[0].balance += 1,
+            |input| input.balances[0].group = Some(0),
+            |input| input.balances.truncate(3),
+            |input| input.weight_policy = WeightPolicy::Capped(0),
+            |input| input.mode = RaffleMode::Ranking,
+            |input| input.num_winners = 3,
+            |input| input.quotas.clear(),
+            |input| input.commitment_tree = TreeKind::Positional(LeafEncoding::Abi),
+            |input| input.winners_tree = TreeKind::OpenZeppelin,
+            |input| input.raffle_id = 2,
+            |input| input.chain_id = 2,
+            |input| input.raffle_contract[0] = 0,
+            |input| input.inline_winners = Some(2),
+            |input| {
+                let entries = derive_entries(&input.balances, input.weight_policy);
+                input.sublinear = Some(get_sublinear_draw(
+                    2,
+                    input.seed,
+                    &entries,
+                    input.commitment_tree,
+                ));
+            },
+        ];
+        for (i, variant) in variants.into_iter().enumerate() {
+            let mut input = input();
+            variant(&mut input);
+            assert_ne!(input_hash(&input), hash, "variant {i}");
+        }
+    }
+}
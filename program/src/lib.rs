@@ -0,0 +1,103 @@
+//! Weighted raffle drawn inside the SP1 zkVM.
+//!
+//! The guest program in `main.rs` is a thin wrapper around this library, which is also used by
+//! the host tooling in `script` to reproduce the roots committed by the program. So are the shard
+//! and aggregation programs in `shard-program` and `aggregation-program` (see `shard`).
+//!
+//! The public values it commits are defined in `public-values`, which consumers can use on its own.
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod fingerprint;
+pub mod merkle;
+pub mod raffle;
+pub mod shard;
+#[cfg(test)]
+mod test_util;
+
+use merkle::TreeKind;
+use raffle::{
+    bind_seed,
+    sublinear::SublinearDraw,
+    weight::{Balance, WeightPolicy},
+    Quota,
+};
+use serde::{Deserialize, Serialize};
+pub use weighted_raffle_public_values::{
+    self as public_values, PublicValuesStruct, PublicValuesTuple, VERSION,
+};
+
+// What the program draws from the entries
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaffleMode {
+    // Draw num_winners winners (subject to the quotas) and commit the winners' Merkle root
+    Winners,
+    // Rank every entry in draw order and commit the ranking's Merkle root
+    Ranking,
+    // Draw num_winners winners from the entries hit by the draw, proven against a given commitment
+    // root (see `raffle::sublinear`), and commit the winners' Merkle root
+    Sublinear,
+    // Only validate the entries and commit their root, total weight and number of entries (see
+    // `merkle::register_entries`), for later sublinear draws
+    Register,
+}
+
+impl RaffleMode {
+    // Identifier committed in the public values
+    pub fn id(&self) -> u8 {
+        match self {
+            RaffleMode::Winners => public_values::WINNERS_MODE,
+            RaffleMode::Ranking => public_values::RANKING_MODE,
+            RaffleMode::Sublinear => public_values::SUBLINEAR_MODE,
+            RaffleMode::Register => public_values::REGISTER_MODE,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WeightedRaffleProgramInput {
+    pub seed: [u8; 32],
+    pub balances: Vec<Balance>,
+    pub weight_policy: WeightPolicy,
+    pub mode: RaffleMode,
+    pub num_winners: u64,
+    pub quotas: Vec<Quota>,
+    // Tree kind of the commitment and balances trees, which cannot be sparse
+    pub commitment_tree: TreeKind,
+    // Tree kind of the winners and ranking trees, which can only be sparse for the winners
+    pub winners_tree: TreeKind,
+    // Commitment root, total weight, number of entries and hit entries in sublinear mode, where
+    // the balances are not read (and their root is zero)
+    pub sublinear: Option<SublinearDraw>,
+    // Raffle the draw is bound to, mixed into the seed and committed in the public values
+    pub raffle_id: u64,
+    pub chain_id: u64,
+    pub raffle_contract: [u8; 20],
+    // Commit the winners (or the ranking) inline in the public values, failing if there are more
+    // than this many, to keep the calldata of the contracts paying them out bounded
+    pub inline_winners: Option<u64>,
+}
+
+impl WeightedRaffleProgramInput {
+    // Sparse trees are keyed by address, so they can only commit the (distinct) winners and not
+    // the entries, balances or ranking
+    pub fn check_trees(&self) {
+        assert!(
+            !matches!(self.commitment_tree, TreeKind::Sparse(_)),
+            "sparse trees can only commit the winners, not the entries"
+        );
+        assert!(
+            self.mode != RaffleMode::Ranking || !matches!(self.winners_tree, TreeKind::Sparse(_)),
+            "sparse trees can only commit the winners, not the ranking"
+        );
+    }
+
+    // Seed the winners and the ranking are drawn with
+    pub fn draw_seed(&self) -> [u8; 32] {
+        bind_seed(
+            self.seed,
+            self.chain_id,
+            self.raffle_contract,
+            self.raffle_id,
+        )
+    }
+}
@@ -2,37 +2,142 @@
 #[cfg(not(test))]
 sp1_zkvm::entrypoint!(main);
 
-mod merkle;
-mod raffle;
-use alloy_sol_types::{sol, SolType};
-use merkle::{get_commitment_root, get_winners_root};
-use raffle::Entry;
-use serde::{Deserialize, Serialize};
-
-type PublicValuesTuple = sol! {
-    // commitment_root, seed, winners_root
-    tuple(bytes32,bytes32,bytes32)
+use alloy_primitives::Address;
+use alloy_sol_types::SolType;
+use weighted_raffle_program::{
+    fingerprint::input_hash,
+    merkle::{get_balances_root, get_drawn_winners_root, get_ranking_leaves, register_entries},
+    raffle::{
+        draw, quotas_hash, rank, sublinear::draw_sublinear, weight::derive_entries, FEISTEL_ROUNDS,
+    },
+    PublicValuesStruct, PublicValuesTuple, RaffleMode, WeightedRaffleProgramInput, VERSION,
 };
 
-#[derive(Serialize, Deserialize)]
-struct WeightedRaffleProgramInput {
-    seed: [u8; 32],
-    entries: Vec<Entry>,
-    num_winners: u64,
-}
-
 pub fn main() {
     let input = sp1_zkvm::io::read::<WeightedRaffleProgramInput>();
+    input.check_trees();
 
     println!("cycle-tracker-start: main");
 
-    let commit_root = get_commitment_root(&input.entries);
-    let winners_root = get_winners_root(input.num_winners, input.seed, &input.entries);
+    println!("cycle-tracker-start: input-hash");
+    let input_hash = input_hash(&input);
+    println!("cycle-tracker-end: input-hash");
+    let seed = input.draw_seed();
+
+    let (commit_root, balances_root, winners_root, total_weight, entry_count, winners) = match input
+        .mode
+    {
+        RaffleMode::Sublinear => {
+            let sublinear = input
+                .sublinear
+                .as_ref()
+                .expect("sublinear draws need the hit entries");
+            assert!(
+                input.quotas.is_empty(),
+                "quotas are not supported in sublinear draws"
+            );
+            let winners = draw_sublinear(input.num_winners, seed, sublinear, input.commitment_tree);
+            (
+                sublinear.commitment_root,
+                [0; 32],
+                get_drawn_winners_root(&winners, input.winners_tree),
+                sublinear.total_weight,
+                sublinear.entry_count,
+                winners,
+            )
+        }
+        RaffleMode::Register => {
+            let entries = derive_entries(&input.balances, input.weight_policy);
+            let registration = register_entries(&entries, input.commitment_tree);
+            (
+                registration.commitment_root,
+                [0; 32],
+                [0; 32],
+                registration.total_weight,
+                registration.entry_count,
+                vec![],
+            )
+        }
+        RaffleMode::Winners | RaffleMode::Ranking => {
+            let entries = derive_entries(&input.balances, input.weight_policy);
+            // The commitment root validates the entries, so the balances they were derived from
+            // are only hashed once valid
+            println!("cycle-tracker-start: commitment-root");
+            let registration = register_entries(&entries, input.commitment_tree);
+            println!("cycle-tracker-end: commitment-root");
+            let balances_root = get_balances_root(&input.balances, input.commitment_tree);
+            // The ranking holds every entry
+            let (winners_root, winners) = if input.mode == RaffleMode::Ranking {
+                assert!(
+                    input.quotas.is_empty(),
+                    "quotas are not supported in rankings"
+                );
+                let ranking = rank(seed, &entries);
+                (
+                    input
+                        .winners_tree
+                        .root(&get_ranking_leaves(&ranking, input.winners_tree))
+                        .expect("sparse trees can only commit the winners, not the ranking"),
+                    ranking,
+                )
+            } else {
+                let winners = draw(input.num_winners, &input.quotas, seed, &entries);
+                (
+                    get_drawn_winners_root(&winners, input.winners_tree),
+                    winners,
+                )
+            };
+            (
+                registration.commitment_root,
+                balances_root,
+                winners_root,
+                registration.total_weight,
+                registration.entry_count,
+                winners,
+            )
+        }
+    };
+
+    // Winners (or ranking) committed inline, up to the given limit
+    let inline_winners: Vec<Address> = match input.inline_winners {
+        None => vec![],
+        Some(limit) => {
+            assert!(
+                winners.len() as u64 <= limit,
+                "{} winners exceed the inline limit of {limit}",
+                winners.len()
+            );
+            winners.iter().copied().map(Address::from).collect()
+        }
+    };
 
     println!("cycle-tracker-end: main");
 
     // Encode the public values of the program.
-    let bytes = PublicValuesTuple::abi_encode(&(commit_root, input.seed, winners_root));
+    let values = PublicValuesStruct {
+        commitmentRoot: commit_root.into(),
+        seed: input.seed.into(),
+        winnersRoot: winners_root.into(),
+        balancesRoot: balances_root.into(),
+        weightPolicy: input.weight_policy.id(),
+        weightParam: input.weight_policy.param(),
+        quotasHash: quotas_hash(&input.quotas).into(),
+        mode: input.mode.id(),
+        commitmentTree: input.commitment_tree.id(),
+        winnersTree: input.winners_tree.id(),
+        commitmentLeafEncoding: input.commitment_tree.encoding().id(),
+        winnersLeafEncoding: input.winners_tree.encoding().id(),
+        totalWeight: total_weight,
+        entryCount: entry_count,
+        numWinners: winners.len() as u64,
+        version: VERSION,
+        rounds: FEISTEL_ROUNDS as u8,
+        raffleId: input.raffle_id,
+        chainId: input.chain_id,
+        raffleContract: input.raffle_contract.into(),
+        inputHash: input_hash.into(),
+    };
+    let bytes = PublicValuesTuple::abi_encode_params(&(values, inline_winners));
     // Commit to the public values of the program.
     sp1_zkvm::io::commit_slice(&bytes);
 }
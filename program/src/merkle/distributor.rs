@@ -0,0 +1,124 @@
+// Merkle tree compatible with Uniswap's merkle-distributor (BalanceTree), so that claims can be
+// checked by MerkleDistributor.claim with MerkleProof.verify:
+// - leaves are H(abi.encodePacked(uint256 index, address account, uint256 amount)), hashed once
+// - leaves are sorted before building the tree
+// - internal nodes are H(min(a, b) || max(a, b)), and the last node of an odd layer is promoted
+use super::{standard, Keccak256Algorithm};
+use rs_merkle::Hasher;
+
+pub fn hash_claim(index: u64, address: &[u8; 20], amount: u128) -> [u8; 32] {
+    let mut packed = Vec::with_capacity(32 + 20 + 32);
+    packed.extend([0; 24]);
+    packed.extend(index.to_be_bytes());
+    packed.extend(address);
+    packed.extend([0; 16]);
+    packed.extend(amount.to_be_bytes());
+    Keccak256Algorithm::hash(&packed)
+}
+
+pub struct DistributorTree {
+    // Sorted leaves first, root last
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl DistributorTree {
+    // Build the tree from hashed claims (see `hash_claim`)
+    pub fn from_leaves(leaves: &[[u8; 32]]) -> Self {
+        assert!(!leaves.is_empty(), "expected non-zero number of leaves");
+
+        let mut layer = leaves.to_vec();
+        layer.sort();
+        layer.dedup();
+        let mut layers = vec![layer];
+        while layers[layers.len() - 1].len() > 1 {
+            let next = layers[layers.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => standard::hash_pair(left, right),
+                    [node] => *node,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+
+        DistributorTree { layers }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.layers[self.layers.len() - 1][0]
+    }
+
+    // Sibling path of a leaf, from the leaf up to the root, skipping promoted nodes
+    pub fn proof(&self, leaf: &[u8; 32]) -> Vec<[u8; 32]> {
+        let mut i = self.layers[0]
+            .binary_search(leaf)
+            .expect("leaf is not part of the tree");
+        let mut proof = vec![];
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(i ^ 1) {
+                proof.push(*sibling);
+            }
+            i /= 2;
+        }
+        proof
+    }
+}
+
+// Equivalent of MerkleProof.verify(proof, root, leaf), as called by MerkleDistributor.claim
+pub fn verify(root: &[u8; 32], leaf: &[u8; 32], proof: &[[u8; 32]]) -> bool {
+    standard::verify(root, leaf, proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::from_hex;
+
+    // Computed with merkle-distributor's BalanceTree for the claims (0, 0x11..11, 100),
+    // (1, 0x22..22, 200) and (2, 0x33..33, 300)
+    #[test]
+    fn test_balance_tree_vector() {
+        let leaves = [
+            hash_claim(0, &[0x11; 20], 100),
+            hash_claim(1, &[0x22; 20], 200),
+            hash_claim(2, &[0x33; 20], 300),
+        ];
+        assert_eq!(
+            leaves,
+            [
+                from_hex("0x81d0c9e91aa5221ffb35dc43b148c071fd8544baf431e949e57286f2bf8af3f8"),
+                from_hex("0xd506e0951d150da81e8a422e4117884b47e950c03d8b370fad019b4d761ddc08"),
+                from_hex("0x4ca86745bec882d8e7daacee107a8d0ad24dff94e0f5e0033a31165644b1ea38"),
+            ]
+        );
+
+        let tree = DistributorTree::from_leaves(&leaves);
+        let root = from_hex("0x1993dd7add1b44f616cd752f8e5b840e9598db608fde74fdcb477d4373f9873d");
+        assert_eq!(tree.root(), root);
+        assert_eq!(tree.proof(&leaves[0]), vec![leaves[2], leaves[1]]);
+        assert_eq!(
+            tree.proof(&leaves[1]),
+            vec![from_hex(
+                "0xf82f8b705384b531361af5851234cdc1b64411ea014082864416360902c382f8"
+            )]
+        );
+        assert_eq!(tree.proof(&leaves[2]), vec![leaves[0], leaves[1]]);
+        for leaf in &leaves {
+            assert!(verify(&root, leaf, &tree.proof(leaf)));
+        }
+        assert!(!verify(
+            &root,
+            &hash_claim(0, &[0x11; 20], 101),
+            &tree.proof(&leaves[0])
+        ));
+    }
+
+    #[test]
+    fn test_single_claim() {
+        let leaf = hash_claim(0, &[1; 20], 1);
+        let tree = DistributorTree::from_leaves(&[leaf]);
+        assert_eq!(tree.root(), leaf);
+        assert!(tree.proof(&leaf).is_empty());
+    }
+}
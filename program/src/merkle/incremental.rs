@@ -0,0 +1,184 @@
+// Append-only Merkle tree of fixed depth as maintained on-chain by deposit-contract style
+// registries: the contract only stores one node per level (the "branch") and the number of
+// leaves, and empty positions are filled with the roots of empty subtrees (zero hashes):
+// - zero_hashes[0] = bytes32(0), zero_hashes[h + 1] = H(zero_hashes[h] || zero_hashes[h])
+// - internal nodes are H(left || right)
+// - the root is the root of the full tree of 2^DEPTH leaves (no length mix-in)
+use super::Keccak256Algorithm;
+use rs_merkle::Hasher;
+
+pub const DEPTH: usize = 32;
+
+// Roots of empty subtrees of every height
+pub fn zero_hashes() -> [[u8; 32]; DEPTH + 1] {
+    let mut zero_hashes = [[0u8; 32]; DEPTH + 1];
+    for h in 0..DEPTH {
+        zero_hashes[h + 1] =
+            Keccak256Algorithm::concat_and_hash(&zero_hashes[h], Some(&zero_hashes[h]));
+    }
+    zero_hashes
+}
+
+pub struct IncrementalMerkleTree {
+    branch: [[u8; 32]; DEPTH],
+    count: u64,
+    zero_hashes: [[u8; 32]; DEPTH + 1],
+}
+
+impl Default for IncrementalMerkleTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IncrementalMerkleTree {
+    pub fn new() -> Self {
+        IncrementalMerkleTree {
+            branch: [[0u8; 32]; DEPTH],
+            count: 0,
+            zero_hashes: zero_hashes(),
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    // Equivalent of the contract's deposit(): O(DEPTH) hashes, updates one node of the branch
+    pub fn insert(&mut self, leaf: [u8; 32]) {
+        assert!(self.count < (1 << DEPTH) - 1, "merkle tree full");
+        self.count += 1;
+        let mut node = leaf;
+        let mut size = self.count;
+        for h in 0..DEPTH {
+            if size & 1 == 1 {
+                self.branch[h] = node;
+                return;
+            }
+            node = Keccak256Algorithm::concat_and_hash(&self.branch[h], Some(&node));
+            size /= 2;
+        }
+    }
+
+    // Equivalent of the contract's get_root()
+    pub fn root(&self) -> [u8; 32] {
+        let mut node = [0u8; 32];
+        let mut size = self.count;
+        for h in 0..DEPTH {
+            node = if size & 1 == 1 {
+                Keccak256Algorithm::concat_and_hash(&self.branch[h], Some(&node))
+            } else {
+                Keccak256Algorithm::concat_and_hash(&node, Some(&self.zero_hashes[h]))
+            };
+            size /= 2;
+        }
+        node
+    }
+}
+
+pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut tree = IncrementalMerkleTree::new();
+    for leaf in leaves {
+        tree.insert(*leaf);
+    }
+    tree.root()
+}
+
+// Sibling paths of every leaf, DEPTH hashes each (zero hashes where the sibling is empty)
+pub fn proofs(leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+    let zero_hashes = zero_hashes();
+    let mut proofs = vec![Vec::with_capacity(DEPTH); leaves.len()];
+    let mut level = leaves.to_vec();
+    for zero_hash in &zero_hashes[..DEPTH] {
+        for (index, proof) in proofs.iter_mut().enumerate() {
+            let position = index >> proof.len();
+            proof.push(*level.get(position ^ 1).unwrap_or(zero_hash));
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| {
+                Keccak256Algorithm::concat_and_hash(
+                    &pair[0],
+                    Some(pair.get(1).unwrap_or(zero_hash)),
+                )
+            })
+            .collect();
+    }
+    proofs
+}
+
+// Check the sibling path of the leaf at `index`
+pub fn verify(root: &[u8; 32], leaf: &[u8; 32], index: u64, proof: &[[u8; 32]]) -> bool {
+    if proof.len() != DEPTH || index >= 1 << DEPTH {
+        return false;
+    }
+    let computed = proof.iter().enumerate().fold(*leaf, |node, (h, sibling)| {
+        if (index >> h) & 1 == 1 {
+            Keccak256Algorithm::concat_and_hash(sibling, Some(&node))
+        } else {
+            Keccak256Algorithm::concat_and_hash(&node, Some(sibling))
+        }
+    });
+    computed == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Root of the full tree, padding every level with the zero hash of its height
+    fn naive_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+        let zero_hashes = zero_hashes();
+        let mut level = leaves.to_vec();
+        for zero_hash in &zero_hashes[..DEPTH] {
+            if level.is_empty() {
+                level.push(*zero_hash);
+            }
+            if level.len() % 2 == 1 {
+                level.push(*zero_hash);
+            }
+            level = level
+                .chunks(2)
+                .map(|pair| Keccak256Algorithm::concat_and_hash(&pair[0], Some(&pair[1])))
+                .collect();
+        }
+        level[0]
+    }
+
+    #[test]
+    fn test_zero_hashes() {
+        // keccak256(abi.encodePacked(bytes32(0), bytes32(0)))
+        assert_eq!(
+            zero_hashes()[1],
+            [
+                0xad, 0x32, 0x28, 0xb6, 0x76, 0xf7, 0xd3, 0xcd, 0x42, 0x84, 0xa5, 0x44, 0x3f, 0x17,
+                0xf1, 0x96, 0x2b, 0x36, 0xe4, 0x91, 0xb3, 0x0a, 0x40, 0xb2, 0x40, 0x58, 0x49, 0xe5,
+                0x97, 0xba, 0x5f, 0xb5
+            ]
+        );
+        assert_eq!(IncrementalMerkleTree::new().root(), zero_hashes()[DEPTH]);
+    }
+
+    #[test]
+    fn test_incremental_root_matches_full_tree() {
+        let leaves: Vec<[u8; 32]> = (1..=33u8).map(|i| [i; 32]).collect();
+        let mut tree = IncrementalMerkleTree::new();
+        for (i, leaf) in leaves.iter().enumerate() {
+            tree.insert(*leaf);
+            assert_eq!(tree.count(), i as u64 + 1);
+            assert_eq!(tree.root(), naive_root(&leaves[..=i]));
+        }
+    }
+
+    #[test]
+    fn test_incremental_proofs() {
+        for num_leaves in 1..=9u8 {
+            let leaves: Vec<[u8; 32]> = (1..=num_leaves).map(|i| [i; 32]).collect();
+            let root = root(&leaves);
+            for (i, (leaf, proof)) in leaves.iter().zip(proofs(&leaves)).enumerate() {
+                assert!(verify(&root, leaf, i as u64, &proof), "invalid proof");
+                assert!(!verify(&root, leaf, i as u64 ^ 1, &proof), "forged proof");
+            }
+        }
+    }
+}
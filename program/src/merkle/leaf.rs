@@ -0,0 +1,203 @@
+use crate::raffle::{weight::Balance, Entry};
+use alloy_primitives::U256;
+use alloy_sol_types::{sol_data, SolType};
+use serde::{Deserialize, Serialize};
+
+// A typed value committed to by a leaf
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeafField {
+    Address([u8; 20]),
+    Uint64(u64),
+    Uint32(u32),
+}
+
+// Commitment leaf: (address, start, end [, group])
+pub fn entry_leaf(entry: &Entry) -> Vec<LeafField> {
+    let mut fields = vec![
+        LeafField::Address(entry.address),
+        LeafField::Uint64(entry.start),
+        LeafField::Uint64(entry.end),
+    ];
+    if let Some(group) = entry.group {
+        fields.push(LeafField::Uint32(group));
+    }
+    fields
+}
+
+// Balances leaf: (address, balance [, group])
+pub fn balance_leaf(balance: &Balance) -> Vec<LeafField> {
+    let mut fields = vec![
+        LeafField::Address(balance.address),
+        LeafField::Uint64(balance.balance),
+    ];
+    if let Some(group) = balance.group {
+        fields.push(LeafField::Uint32(group));
+    }
+    fields
+}
+
+// Winners leaf: (address)
+pub fn winner_leaf(address: &[u8; 20]) -> Vec<LeafField> {
+    vec![LeafField::Address(*address)]
+}
+
+// Ranking leaf: (rank, address)
+pub fn rank_leaf(rank: u64, address: &[u8; 20]) -> Vec<LeafField> {
+    vec![LeafField::Uint64(rank), LeafField::Address(*address)]
+}
+
+// How the fields of a leaf are serialised before being hashed, so that contracts can rebuild
+// leaves byte-for-byte
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeafEncoding {
+    // abi.encodePacked with the fields' own types, e.g. abi.encodePacked(address, uint64, uint64)
+    // for an entry, see `encode_packed`
+    Packed,
+    // abi.encode with every integer widened to uint256, e.g. abi.encode(address, uint256,
+    // uint256) for an entry, see `abi_encode`
+    Abi,
+}
+
+impl LeafEncoding {
+    // Identifier committed in the public values
+    pub fn id(&self) -> u8 {
+        match self {
+            LeafEncoding::Packed => 0,
+            LeafEncoding::Abi => 1,
+        }
+    }
+
+    pub fn encode(&self, fields: &[LeafField]) -> Vec<u8> {
+        match self {
+            LeafEncoding::Packed => encode_packed(fields),
+            LeafEncoding::Abi => abi_encode(fields),
+        }
+    }
+}
+
+// abi.encodePacked(address, uint64, ...): big-endian concatenation of the fields at their own
+// width (address: 20 bytes, uint32: 4, uint64: 8)
+pub fn encode_packed(fields: &[LeafField]) -> Vec<u8> {
+    let mut out = vec![];
+    for field in fields {
+        match field {
+            LeafField::Address(address) => {
+                out.extend(sol_data::Address::abi_encode_packed(address))
+            }
+            LeafField::Uint64(value) => out.extend(sol_data::Uint::<64>::abi_encode_packed(value)),
+            LeafField::Uint32(value) => out.extend(sol_data::Uint::<32>::abi_encode_packed(value)),
+        }
+    }
+    out
+}
+
+// abi.encode(address, uint256, ...): every field is left-padded to 32 bytes, integers being
+// encoded as uint256
+pub fn abi_encode(fields: &[LeafField]) -> Vec<u8> {
+    let mut out = vec![];
+    for field in fields {
+        let value = match field {
+            LeafField::Address(address) => {
+                out.extend(sol_data::Address::abi_encode(address));
+                continue;
+            }
+            LeafField::Uint64(value) => U256::from(*value),
+            LeafField::Uint32(value) => U256::from(*value),
+        };
+        out.extend(sol_data::Uint::<256>::abi_encode(&value));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_sol_types::sol;
+
+    fn u256(value: u64) -> U256 {
+        U256::from(value)
+    }
+
+    #[test]
+    fn test_encodings_match_solidity() {
+        let entry = Entry {
+            address: [0x11; 20],
+            start: 10,
+            end: u64::MAX,
+            group: Some(7),
+        };
+        let address = alloy_primitives::Address::from(entry.address);
+
+        // abi.encodePacked(address, uint64, uint64, uint32)
+        type PackedEntry = sol! { tuple(address, uint64, uint64, uint32) };
+        let values = (address, entry.start, entry.end, 7u32);
+        assert_eq!(
+            LeafEncoding::Packed.encode(&entry_leaf(&entry)),
+            PackedEntry::abi_encode_packed(&values)
+        );
+        // Ungrouped entries are the legacy address || start || end leaves
+        let ungrouped = Entry {
+            group: None,
+            ..entry
+        };
+        let mut legacy = entry.address.to_vec();
+        legacy.extend_from_slice(&entry.start.to_be_bytes());
+        legacy.extend_from_slice(&entry.end.to_be_bytes());
+        assert_eq!(LeafEncoding::Packed.encode(&entry_leaf(&ungrouped)), legacy);
+
+        // abi.encode(address, uint256, uint256, uint256)
+        type AbiEntry = sol! { tuple(address, uint256, uint256, uint256) };
+        let values = (address, u256(entry.start), u256(entry.end), u256(7));
+        assert_eq!(
+            LeafEncoding::Abi.encode(&entry_leaf(&entry)),
+            AbiEntry::abi_encode_params(&values)
+        );
+        type AbiUngroupedEntry = sol! { tuple(address, uint256, uint256) };
+        let values = (address, u256(entry.start), u256(entry.end));
+        assert_eq!(
+            LeafEncoding::Abi.encode(&entry_leaf(&ungrouped)),
+            AbiUngroupedEntry::abi_encode_params(&values)
+        );
+
+        // Balances: abi.encodePacked(address, uint64) / abi.encode(address, uint256)
+        let balance = Balance {
+            address: [0x33; 20],
+            balance: 1234,
+            group: None,
+        };
+        let address = alloy_primitives::Address::from(balance.address);
+        type PackedBalance = sol! { tuple(address, uint64) };
+        type AbiBalance = sol! { tuple(address, uint256) };
+        assert_eq!(
+            LeafEncoding::Packed.encode(&balance_leaf(&balance)),
+            PackedBalance::abi_encode_packed(&(address, balance.balance))
+        );
+        assert_eq!(
+            LeafEncoding::Abi.encode(&balance_leaf(&balance)),
+            AbiBalance::abi_encode_params(&(address, u256(balance.balance)))
+        );
+
+        // Winners: abi.encodePacked(address) / abi.encode(address)
+        type Winner = sol! { tuple(address,) };
+        assert_eq!(
+            LeafEncoding::Packed.encode(&winner_leaf(&balance.address)),
+            Winner::abi_encode_packed(&(address,))
+        );
+        assert_eq!(
+            LeafEncoding::Abi.encode(&winner_leaf(&balance.address)),
+            Winner::abi_encode_params(&(address,))
+        );
+
+        // Ranks: abi.encodePacked(uint64, address) / abi.encode(uint256, address)
+        type PackedRank = sol! { tuple(uint64, address) };
+        type AbiRank = sol! { tuple(uint256, address) };
+        assert_eq!(
+            LeafEncoding::Packed.encode(&rank_leaf(5, &balance.address)),
+            PackedRank::abi_encode_packed(&(5, address))
+        );
+        assert_eq!(
+            LeafEncoding::Abi.encode(&rank_leaf(5, &balance.address)),
+            AbiRank::abi_encode_params(&(u256(5), address))
+        );
+    }
+}
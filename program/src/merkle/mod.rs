@@ -1,6 +1,31 @@
-use crate::raffle::{draw, Entry};
+pub mod distributor;
+pub mod incremental;
+pub mod leaf;
+pub mod poseidon;
+pub mod proof;
+pub mod sparse;
+pub mod standard;
+pub mod stream;
+pub mod tagged;
+
+use crate::raffle::{
+    draw,
+    index::{check_entry, EntryIndex, InvalidEntries},
+    rank,
+    weight::Balance,
+    Entry, Quota,
+};
+use distributor::DistributorTree;
+use leaf::{balance_leaf, entry_leaf, rank_leaf, winner_leaf, LeafEncoding, LeafField};
+use poseidon::Poseidon254Algorithm;
 use rs_merkle::{Hasher, MerkleTree};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use sha3::{Digest, Keccak256};
+use sparse::SparseMerkleTree;
+use standard::StandardMerkleTree;
+use stream::StreamingRoot;
+use tagged::TaggedKeccak256Algorithm;
 
 #[derive(Clone)]
 pub struct Keccak256Algorithm;
@@ -14,93 +39,339 @@ impl Hasher for Keccak256Algorithm {
     }
 }
 
+// SHA-256 through the patched sha2 crate, which the zkVM runs as a precompile
+#[derive(Clone)]
+pub struct Sha256Algorithm;
+
+impl Hasher for Sha256Algorithm {
+    type Hash = [u8; 32];
+    fn hash(data: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        hasher.finalize().into()
+    }
+}
+
 pub fn get_merkle_root(leaves: Vec<[u8; 32]>) -> [u8; 32] {
     let winners_tree: MerkleTree<Keccak256Algorithm> =
         MerkleTree::<Keccak256Algorithm>::from_leaves(&leaves);
     winners_tree.root().ok_or("failed to compute root").unwrap()
 }
 
-fn is_ordered(a: &[u8; 20], b: &[u8; 20]) -> bool {
-    for i in 0..20 {
-        match (a[i], b[i]) {
-            (left, right) if left < right => return true,
-            (left, right) if left > right => return false,
-            _ => continue,
+// Layout of a Merkle tree and how its leaves are hashed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeKind {
+    // Leaves are H(encoding(values)), pairs are hashed in positional order and odd nodes are
+    // promoted to the next level (rs_merkle)
+    Positional(LeafEncoding),
+    // OpenZeppelin StandardMerkleTree, see `standard` (leaves are always abi-encoded)
+    OpenZeppelin,
+    // Positional tree with domain-separated leaves and nodes, committing to its number of leaves,
+    // see `tagged`
+    Tagged(LeafEncoding),
+    // Fixed-depth append-only tree padded with zero hashes, as maintained by deposit-contract
+    // style registries, see `incremental`
+    Incremental(LeafEncoding),
+    // Sparse tree of depth 256 keyed by address, proving absence as well as membership, see
+    // `sparse` (winners tree only)
+    Sparse(LeafEncoding),
+    // Positional tree hashed with Poseidon over BN254 (circomlib parameters), for claims proven in
+    // Circom/Noir circuits, see `poseidon` (leaves are always abi-encoded, one field element per
+    // value)
+    Poseidon,
+    // Positional tree hashed with SHA-256 instead of keccak256, for verifiers where only SHA-256 is
+    // cheap
+    Sha256(LeafEncoding),
+}
+
+impl TreeKind {
+    // Identifier committed in the public values
+    pub fn id(&self) -> u8 {
+        match self {
+            TreeKind::Positional(_) => 0,
+            TreeKind::OpenZeppelin => 1,
+            TreeKind::Tagged(_) => 2,
+            TreeKind::Incremental(_) => 3,
+            TreeKind::Sparse(_) => 4,
+            TreeKind::Poseidon => 5,
+            TreeKind::Sha256(_) => 6,
+        }
+    }
+
+    // Serialisation of the leaves' values (its identifier is committed in the public values)
+    pub fn encoding(&self) -> LeafEncoding {
+        match self {
+            TreeKind::Positional(encoding)
+            | TreeKind::Tagged(encoding)
+            | TreeKind::Incremental(encoding)
+            | TreeKind::Sparse(encoding)
+            | TreeKind::Sha256(encoding) => *encoding,
+            TreeKind::OpenZeppelin | TreeKind::Poseidon => LeafEncoding::Abi,
+        }
+    }
+
+    pub fn hash_leaf(&self, fields: &[LeafField]) -> [u8; 32] {
+        match self {
+            TreeKind::Positional(encoding)
+            | TreeKind::Incremental(encoding)
+            | TreeKind::Sparse(encoding) => Keccak256Algorithm::hash(&encoding.encode(fields)),
+            TreeKind::OpenZeppelin => standard::hash_leaf(fields),
+            TreeKind::Tagged(encoding) => tagged::hash_leaf(&encoding.encode(fields)),
+            TreeKind::Poseidon => poseidon::hash_leaf(fields),
+            TreeKind::Sha256(encoding) => Sha256Algorithm::hash(&encoding.encode(fields)),
+        }
+    }
+
+    // Root of the tree over hashed leaves, or None for sparse trees, which are keyed by address
+    // rather than built from the leaves alone (see `sparse::SparseMerkleTree`)
+    pub fn root(&self, leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+        if let Some(mut builder) = RootBuilder::new(*self) {
+            for leaf in leaves {
+                builder.push(*leaf);
+            }
+            return Some(builder.root());
+        }
+        match self {
+            TreeKind::OpenZeppelin => Some(StandardMerkleTree::from_leaves(leaves).root()),
+            TreeKind::Sparse(_) => None,
+            _ => unreachable!(),
+        }
+    }
+
+    // Sibling paths of every hashed leaf, from the leaf up to the root, or None for sparse trees
+    // (see `root`)
+    // In positional trees, levels where the node has no sibling (odd node promoted to the next
+    // level) contribute no hash
+    pub fn proofs(&self, leaves: &[[u8; 32]]) -> Option<Vec<Vec<[u8; 32]>>> {
+        let proofs = match self {
+            TreeKind::Positional(_) => {
+                let tree = MerkleTree::<Keccak256Algorithm>::from_leaves(leaves);
+                (0..leaves.len())
+                    .map(|index| tree.proof(&[index]).proof_hashes().to_vec())
+                    .collect()
+            }
+            TreeKind::OpenZeppelin => {
+                let tree = StandardMerkleTree::from_leaves(leaves);
+                (0..leaves.len()).map(|index| tree.proof(index)).collect()
+            }
+            TreeKind::Tagged(_) => tagged::proofs(leaves),
+            TreeKind::Incremental(_) => incremental::proofs(leaves),
+            TreeKind::Poseidon => poseidon::proofs(leaves),
+            TreeKind::Sha256(_) => {
+                let tree = MerkleTree::<Sha256Algorithm>::from_leaves(leaves);
+                (0..leaves.len())
+                    .map(|index| tree.proof(&[index]).proof_hashes().to_vec())
+                    .collect()
+            }
+            TreeKind::Sparse(_) => return None,
+        };
+        Some(proofs)
+    }
+}
+
+// Root of a tree built one leaf at a time in O(log n) memory, for the tree kinds whose nodes do
+// not depend on leaves yet to come (OpenZeppelin trees sort their leaves and sparse trees are keyed
+// by address)
+pub enum RootBuilder {
+    Positional(StreamingRoot<Keccak256Algorithm>),
+    Tagged(StreamingRoot<TaggedKeccak256Algorithm>),
+    Incremental(Box<incremental::IncrementalMerkleTree>),
+    Poseidon(StreamingRoot<Poseidon254Algorithm>),
+    Sha256(StreamingRoot<Sha256Algorithm>),
+}
+
+impl RootBuilder {
+    pub fn new(kind: TreeKind) -> Option<Self> {
+        match kind {
+            TreeKind::Positional(_) => Some(RootBuilder::Positional(StreamingRoot::new())),
+            TreeKind::Tagged(_) => Some(RootBuilder::Tagged(StreamingRoot::new())),
+            TreeKind::Incremental(_) => Some(RootBuilder::Incremental(Box::default())),
+            TreeKind::Poseidon => Some(RootBuilder::Poseidon(StreamingRoot::new())),
+            TreeKind::Sha256(_) => Some(RootBuilder::Sha256(StreamingRoot::new())),
+            TreeKind::OpenZeppelin | TreeKind::Sparse(_) => None,
         }
     }
-    false
+
+    pub fn push(&mut self, leaf: [u8; 32]) {
+        match self {
+            RootBuilder::Positional(stream) => stream.push(leaf),
+            RootBuilder::Tagged(stream) => stream.push(leaf),
+            RootBuilder::Incremental(tree) => tree.insert(leaf),
+            RootBuilder::Poseidon(stream) => stream.push(leaf),
+            RootBuilder::Sha256(stream) => stream.push(leaf),
+        }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        match self {
+            RootBuilder::Tagged(stream) => tagged::hash_root(stream.count(), &self.tree_root()),
+            _ => self.tree_root(),
+        }
+    }
+
+    // Root of the positional tree below the root hash of tagged trees (see `tagged::hash_root`),
+    // i.e. the root itself for the other kinds
+    pub fn tree_root(&self) -> [u8; 32] {
+        let root = match self {
+            RootBuilder::Positional(stream) => stream.root(),
+            RootBuilder::Tagged(stream) => stream.root(),
+            RootBuilder::Poseidon(stream) => stream.root(),
+            RootBuilder::Sha256(stream) => stream.root(),
+            RootBuilder::Incremental(tree) => Some(tree.root()),
+        };
+        root.ok_or("failed to compute root").unwrap()
+    }
 }
 
 // Compute Merkle root of original commitment
-// Leaves in the commitment tree are the hashes of the entries i.e. H(address || start || end)
-pub fn get_commitment_root(entries: &[Entry]) -> [u8; 32] {
-    assert!(entries.len() >= 2, "<2 entries");
+// Leaves in the commitment tree are the hashes of the entries i.e. H(address || start || end),
+// or H(address || start || end || group) for entries that belong to a group (see `TreeKind` for
+// how the values are hashed)
+// Entries are validated and hashed one at a time without collecting the leaves, unless the tree
+// kind needs all of them at once
+pub fn get_commitment_root(entries: &[Entry], kind: TreeKind) -> [u8; 32] {
+    let Some(mut builder) = RootBuilder::new(kind) else {
+        return kind
+            .root(&get_commitment_leaves(entries, kind))
+            .expect("sparse trees can only commit the winners, not the entries");
+    };
 
-    let commit_leaves = entries
+    // Invariants: see `get_commitment_leaves`
+    if entries.len() < 2 {
+        panic!("{}", InvalidEntries::TooFew);
+    }
+    let mut last_entry = None;
+    for (i, entry) in entries.iter().enumerate() {
+        check_entry(i, last_entry, entry).unwrap_or_else(|err| panic!("{err}"));
+        builder.push(kind.hash_leaf(&entry_leaf(entry)));
+        last_entry = Some(entry);
+    }
+    builder.root()
+}
+
+// What register mode commits about a list of entries, so that later draws (see
+// `raffle::sublinear`) can trust it without reading the entries again
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registration {
+    pub commitment_root: [u8; 32],
+    pub total_weight: u64,
+    pub entry_count: u64,
+}
+
+// Validate the entries and compute their commitment root (see `get_commitment_root`)
+pub fn register_entries(entries: &[Entry], kind: TreeKind) -> Registration {
+    let commitment_root = get_commitment_root(entries, kind);
+    // The commitment root validated at least 2 entries
+    Registration {
+        commitment_root,
+        total_weight: entries[entries.len() - 1].end,
+        entry_count: entries.len() as u64,
+    }
+}
+
+pub fn get_commitment_leaves(entries: &[Entry], kind: TreeKind) -> Vec<[u8; 32]> {
+    // Invariants: at least 2 entries, the first one starting at 0, positive weights, adjacent
+    // segments and distinct addresses ordered ascendingly
+    EntryIndex::new(entries).unwrap_or_else(|err| panic!("{err}"));
+
+    entries
         .iter()
-        .fold(vec![] as Vec<[u8; 32]>, |mut acc, entry| {
-            // Invariant: first entry must start at 0
-            if acc.is_empty() {
-                assert!(entry.start == 0, "first entry must start at 0");
-            }
-            // Invariant: weight must be positive
-            assert!(entry.start < entry.end, "invalid entry");
-
-            if !acc.is_empty() {
-                let last_entry = &entries[acc.len() - 1];
-                // Invariant: entries must be adjacent segments
-                assert!(last_entry.end == entry.start, "non-adjacent entries");
-                // Invariant: addresses are identities and must be distinct
-                assert!(
-                    is_ordered(&last_entry.address, &entry.address),
-                    "entries must be ordered (asc) by addresses"
-                );
-            }
+        .map(|entry| kind.hash_leaf(&entry_leaf(entry)))
+        .collect()
+}
 
-            // Hash leaf = H(address || start || end)
-            let mut hasher = Keccak256::new();
-            hasher.update(entry.address);
-            hasher.update(entry.start.to_be_bytes());
-            hasher.update(entry.end.to_be_bytes());
-            let leaf: [u8; 32] = hasher.finalize().into();
+// Compute Merkle root of the raw balances the entries were derived from
+// Leaves in the balances tree are the hashes of the balances i.e. H(address || balance [|| group])
+pub fn get_balances_root(balances: &[Balance], kind: TreeKind) -> [u8; 32] {
+    let balances_leaves: Vec<_> = balances
+        .iter()
+        .map(|balance| kind.hash_leaf(&balance_leaf(balance)))
+        .collect();
+    kind.root(&balances_leaves)
+        .expect("sparse trees can only commit the winners, not the balances")
+}
 
-            acc.push(leaf);
-            acc
-        });
-    get_merkle_root(commit_leaves)
+// Leaves in the winners' Merkle tree are the hashes of the winners i.e. H(address), in draw order
+pub fn get_winners_leaves(winners: &[[u8; 20]], kind: TreeKind) -> Vec<[u8; 32]> {
+    winners
+        .iter()
+        .map(|address| kind.hash_leaf(&winner_leaf(address)))
+        .collect()
 }
 
 // Draw winners & commit winners' Merkle root
-// Leaves in the winners' Merkle root are the hashes of the winners i.e. H(address)
-pub fn get_winners_root(num_winners: u64, seed: [u8; 32], entries: &[Entry]) -> [u8; 32] {
-    let winners = draw(num_winners, seed, entries);
-    let winners_leaves: Vec<[u8; 32]> = winners
-        .into_iter()
-        .map(|address| {
-            let mut hasher = Keccak256::new();
-            hasher.update(address);
-            hasher.finalize().into()
-        })
-        .collect();
-    get_merkle_root(winners_leaves)
+pub fn get_winners_root(
+    num_winners: u64,
+    quotas: &[Quota],
+    seed: [u8; 32],
+    entries: &[Entry],
+    kind: TreeKind,
+) -> [u8; 32] {
+    get_drawn_winners_root(&draw(num_winners, quotas, seed, entries), kind)
+}
+
+// Commit the winners' Merkle root of a draw
+pub fn get_drawn_winners_root(winners: &[[u8; 20]], kind: TreeKind) -> [u8; 32] {
+    let leaves = get_winners_leaves(winners, kind);
+    kind.root(&leaves)
+        .unwrap_or_else(|| SparseMerkleTree::new(winners, &leaves).root())
+}
+
+// Leaves in the ranking's Merkle tree are the hashes of the ranked entries i.e. H(rank || address),
+// where rank 0 is the first address drawn
+pub fn get_ranking_leaves(ranking: &[[u8; 20]], kind: TreeKind) -> Vec<[u8; 32]> {
+    ranking
+        .iter()
+        .enumerate()
+        .map(|(rank, address)| kind.hash_leaf(&rank_leaf(rank as u64, address)))
+        .collect()
+}
+
+// Rank all entries & commit the ranking's Merkle root
+pub fn get_ranking_root(seed: [u8; 32], entries: &[Entry], kind: TreeKind) -> [u8; 32] {
+    kind.root(&get_ranking_leaves(&rank(seed, entries), kind))
+        .expect("sparse trees can only commit the winners, not the ranking")
+}
+
+// Leaves of a merkle-distributor tree are the hashes of the winners' claims i.e.
+// H(index || address || amount), where winner `index` is entitled to `amounts[index]`
+pub fn get_claim_leaves(winners: &[[u8; 20]], amounts: &[u128]) -> Vec<[u8; 32]> {
+    assert!(
+        winners.len() == amounts.len(),
+        "expected one amount per winner"
+    );
+    winners
+        .iter()
+        .zip(amounts)
+        .enumerate()
+        .map(|(index, (address, amount))| distributor::hash_claim(index as u64, address, *amount))
+        .collect()
+}
+
+pub fn get_claim_root(winners: &[[u8; 20]], amounts: &[u128]) -> [u8; 32] {
+    DistributorTree::from_leaves(&get_claim_leaves(winners, amounts)).root()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{self, hex};
+    use alloy_primitives::U256;
+    use alloy_sol_types::{sol, SolType};
 
     #[test]
     #[should_panic(expected = "<2 entries")]
     fn test_get_commitment_root_asserts_min_entries() {
         let entries_0 = vec![];
-        get_commitment_root(&entries_0);
+        get_commitment_root(&entries_0, TreeKind::Positional(LeafEncoding::Packed));
 
         let entries_1 = vec![Entry {
             address: [1; 20],
             start: 0,
             end: 10,
+            group: None,
         }];
-        get_commitment_root(&entries_1);
+        get_commitment_root(&entries_1, TreeKind::Positional(LeafEncoding::Packed));
     }
 
     #[test]
@@ -111,14 +382,16 @@ mod tests {
                 address: [1; 20],
                 start: 1, // <-- invalid (must start at 0)
                 end: 10,
+                group: None,
             },
             Entry {
                 address: [2; 20],
                 start: 10,
                 end: 20,
+                group: None,
             },
         ];
-        get_commitment_root(&entries);
+        get_commitment_root(&entries, TreeKind::Positional(LeafEncoding::Packed));
     }
 
     #[test]
@@ -129,14 +402,16 @@ mod tests {
                 address: [1; 20],
                 start: 0,
                 end: 0, // <-- weight = 0 - 0 = 0
+                group: None,
             },
             Entry {
                 address: [2; 20],
                 start: 0,
                 end: 10,
+                group: None,
             },
         ];
-        get_commitment_root(&entries);
+        get_commitment_root(&entries, TreeKind::Positional(LeafEncoding::Packed));
     }
 
     #[test]
@@ -147,14 +422,16 @@ mod tests {
                 address: [1; 20],
                 start: 0,
                 end: 10,
+                group: None,
             },
             Entry {
                 address: [2; 20],
                 start: 10,
                 end: 9, // <-- weight = 9 - 10 = -1
+                group: None,
             },
         ];
-        get_commitment_root(&entries);
+        get_commitment_root(&entries, TreeKind::Positional(LeafEncoding::Packed));
     }
 
     #[test]
@@ -165,14 +442,16 @@ mod tests {
                 address: [1; 20],
                 start: 0,
                 end: 11, // <-- non-adjacent (ends after entry@1)
+                group: None,
             },
             Entry {
                 address: [2; 20],
                 start: 10, // <-- non-adjacent (starts before entry@0)
                 end: 20,
+                group: None,
             },
         ];
-        get_commitment_root(&entries);
+        get_commitment_root(&entries, TreeKind::Positional(LeafEncoding::Packed));
     }
 
     #[test]
@@ -183,14 +462,16 @@ mod tests {
                 address: [0x11; 20],
                 start: 0,
                 end: 10,
+                group: None,
             },
             Entry {
                 address: [0x11; 20], // <-- duplicate
                 start: 10,
                 end: 20,
+                group: None,
             },
         ];
-        get_commitment_root(&entries);
+        get_commitment_root(&entries, TreeKind::Positional(LeafEncoding::Packed));
     }
 
     #[test]
@@ -201,14 +482,16 @@ mod tests {
                 address: [0x22; 20],
                 start: 0,
                 end: 10,
+                group: None,
             },
             Entry {
                 address: [0x11; 20], // <-- ordered descendingly
                 start: 10,
                 end: 20,
+                group: None,
             },
         ];
-        get_commitment_root(&entries);
+        get_commitment_root(&entries, TreeKind::Positional(LeafEncoding::Packed));
     }
 
     #[test]
@@ -224,4 +507,304 @@ mod tests {
         println!("leaves = {:02x?}", leaves);
         println!("root = {:02x?}", get_merkle_root(leaves));
     }
+
+    #[test]
+    fn test_get_commitment_root_binds_group() {
+        let entries = |group| {
+            vec![
+                Entry {
+                    address: [0x11; 20],
+                    start: 0,
+                    end: 10,
+                    group,
+                },
+                Entry {
+                    address: [0x22; 20],
+                    start: 10,
+                    end: 20,
+                    group: None,
+                },
+            ]
+        };
+        let ungrouped =
+            get_commitment_root(&entries(None), TreeKind::Positional(LeafEncoding::Packed));
+        let group_0 = get_commitment_root(
+            &entries(Some(0)),
+            TreeKind::Positional(LeafEncoding::Packed),
+        );
+        let group_1 = get_commitment_root(
+            &entries(Some(1)),
+            TreeKind::Positional(LeafEncoding::Packed),
+        );
+        assert_ne!(ungrouped, group_0);
+        assert_ne!(group_0, group_1);
+    }
+
+    #[test]
+    fn test_register_entries() {
+        let entries = entries();
+        let kind = TreeKind::Tagged(LeafEncoding::Packed);
+        assert_eq!(
+            register_entries(&entries, kind),
+            Registration {
+                commitment_root: get_commitment_root(&entries, kind),
+                total_weight: 26,
+                entry_count: 3,
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "non-adjacent entries")]
+    fn test_register_entries_validates_entries() {
+        let mut entries = entries();
+        entries[2].start -= 1;
+        register_entries(&entries, TreeKind::Positional(LeafEncoding::Packed));
+    }
+
+    #[test]
+    fn test_positional_proofs_odd() {
+        let leaves = vec![[0x11u8; 32], [0x22; 32], [0x33; 32]];
+        let node_01 = Keccak256Algorithm::concat_and_hash(&leaves[0], Some(&leaves[1]));
+        let proofs = TreeKind::Positional(LeafEncoding::Packed)
+            .proofs(&leaves)
+            .unwrap();
+        assert_eq!(proofs[0], vec![leaves[1], leaves[2]]);
+        assert_eq!(proofs[1], vec![leaves[0], leaves[2]]);
+        // leaf@2 has no sibling and is promoted
+        assert_eq!(proofs[2], vec![node_01]);
+    }
+
+    fn entries() -> Vec<Entry> {
+        let mut entries = test_util::entries(&[10, 15, 1]);
+        entries[1].group = Some(3);
+        entries
+    }
+
+    #[test]
+    fn test_positional_roots() {
+        // Roots committed before leaf hashing was parameterised by the tree kind
+        let entries = entries();
+        assert_eq!(
+            hex(&get_commitment_root(
+                &entries,
+                TreeKind::Positional(LeafEncoding::Packed)
+            )),
+            "0c6c0a544daabbf351e68f027464a9a88751620c1471d1b7db9dd3a1279d9707"
+        );
+        assert_eq!(
+            hex(&get_winners_root(
+                2,
+                &[],
+                [7; 32],
+                &entries,
+                TreeKind::Positional(LeafEncoding::Packed)
+            )),
+            "fe14d5a747d0ab5475f0d7af564137f9324343aea23cfbb0d61d851664471d08"
+        );
+        assert_eq!(
+            hex(&get_ranking_root(
+                [7; 32],
+                &entries,
+                TreeKind::Positional(LeafEncoding::Packed)
+            )),
+            "a56fa6f73337d76c4f00b51a421ccae9546d0d2244e218c0c99b39f19e56d6d9"
+        );
+    }
+
+    #[test]
+    fn test_abi_encoded_roots() {
+        // Leaves are H(abi.encode(address, uint256, uint256 [, uint256])) as built by a contract
+        type EntryLeaf = sol! { tuple(address, uint256, uint256) };
+        type GroupedEntryLeaf = sol! { tuple(address, uint256, uint256, uint256) };
+        let entries = entries();
+        let leaves: Vec<[u8; 32]> = entries
+            .iter()
+            .map(|entry| {
+                let (start, end) = (U256::from(entry.start), U256::from(entry.end));
+                Keccak256Algorithm::hash(&match entry.group {
+                    None => EntryLeaf::abi_encode_params(&(entry.address, start, end)),
+                    Some(group) => GroupedEntryLeaf::abi_encode_params(&(
+                        entry.address,
+                        start,
+                        end,
+                        U256::from(group),
+                    )),
+                })
+            })
+            .collect();
+        let kind = TreeKind::Positional(LeafEncoding::Abi);
+        let root = get_commitment_root(&entries, kind);
+        assert_eq!(root, get_merkle_root(leaves));
+        assert_ne!(
+            root,
+            get_commitment_root(&entries, TreeKind::Positional(LeafEncoding::Packed))
+        );
+    }
+
+    #[test]
+    fn test_incremental_roots() {
+        // Entries registered one by one on-chain
+        let entries = entries();
+        let mut registry = incremental::IncrementalMerkleTree::new();
+        for entry in &entries {
+            registry.insert(Keccak256Algorithm::hash(
+                &LeafEncoding::Packed.encode(&entry_leaf(entry)),
+            ));
+        }
+        assert_eq!(
+            get_commitment_root(&entries, TreeKind::Incremental(LeafEncoding::Packed)),
+            registry.root()
+        );
+    }
+
+    #[test]
+    fn test_sparse_winners_root() {
+        let entries = entries();
+        let kind = TreeKind::Sparse(LeafEncoding::Packed);
+        let winners = draw(2, &[], [7; 32], &entries);
+        let leaves = get_winners_leaves(&winners, kind);
+        let root = get_winners_root(2, &[], [7; 32], &entries, kind);
+        assert_eq!(root, SparseMerkleTree::new(&winners, &leaves).root());
+
+        // The loser can prove it did not win
+        let loser = entries
+            .iter()
+            .find(|entry| !winners.contains(&entry.address))
+            .unwrap();
+        let tree = SparseMerkleTree::new(&winners, &leaves);
+        assert!(sparse::verify_non_membership(
+            &root,
+            &loser.address,
+            &tree.prove(&loser.address)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "sparse trees can only commit the winners, not the entries")]
+    fn test_sparse_commitment_root() {
+        get_commitment_root(&entries(), TreeKind::Sparse(LeafEncoding::Packed));
+    }
+
+    #[test]
+    fn test_sparse_root_and_proofs_need_addresses() {
+        let kind = TreeKind::Sparse(LeafEncoding::Packed);
+        let winners = [[0x11; 20], [0x22; 20]];
+        let leaves = get_winners_leaves(&winners, kind);
+        assert_eq!(kind.root(&leaves), None);
+        assert_eq!(kind.proofs(&leaves), None);
+        assert_eq!(
+            get_drawn_winners_root(&winners, kind),
+            SparseMerkleTree::new(&winners, &leaves).root()
+        );
+    }
+
+    #[test]
+    fn test_streamed_commitment_roots() {
+        let entries = test_util::entries(&[3; 37]);
+        for kind in [
+            TreeKind::Positional(LeafEncoding::Packed),
+            TreeKind::Tagged(LeafEncoding::Abi),
+            TreeKind::Incremental(LeafEncoding::Packed),
+            TreeKind::Poseidon,
+            TreeKind::Sha256(LeafEncoding::Packed),
+        ] {
+            let leaves = get_commitment_leaves(&entries, kind);
+            let expected = match kind {
+                TreeKind::Positional(_) => get_merkle_root(leaves),
+                TreeKind::Sha256(_) => MerkleTree::<Sha256Algorithm>::from_leaves(&leaves)
+                    .root()
+                    .unwrap(),
+                TreeKind::Tagged(_) => tagged::root(&leaves),
+                TreeKind::Poseidon => poseidon::root(&leaves),
+                _ => incremental::root(&leaves),
+            };
+            assert_eq!(get_commitment_root(&entries, kind), expected);
+        }
+    }
+
+    #[test]
+    fn test_tagged_roots() {
+        let entries = entries();
+        let leaves: Vec<[u8; 32]> = entries
+            .iter()
+            .map(|entry| tagged::hash_leaf(&LeafEncoding::Packed.encode(&entry_leaf(entry))))
+            .collect();
+        // 3 leaves: the last one is promoted
+        let expected = tagged::hash_root(
+            3,
+            &tagged::hash_node(&tagged::hash_node(&leaves[0], &leaves[1]), &leaves[2]),
+        );
+        assert_eq!(
+            get_commitment_root(&entries, TreeKind::Tagged(LeafEncoding::Packed)),
+            expected
+        );
+        assert_eq!(
+            hex(&expected),
+            "578a5b9e2879c892b59539fe29db05208251e3f762e1b1478a78455de625c74e"
+        );
+        assert_ne!(
+            expected,
+            get_commitment_root(&entries, TreeKind::Positional(LeafEncoding::Packed))
+        );
+    }
+
+    #[test]
+    fn test_sha256_roots() {
+        assert_eq!(
+            hex(&Sha256Algorithm::hash(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let entries = entries();
+        let leaves: Vec<[u8; 32]> = entries
+            .iter()
+            .map(|entry| Sha256::digest(LeafEncoding::Packed.encode(&entry_leaf(entry))).into())
+            .collect();
+        // 3 leaves: the last one is promoted
+        let node_01 = Sha256::digest([leaves[0], leaves[1]].concat()).into();
+        let expected: [u8; 32] = Sha256::digest([node_01, leaves[2]].concat()).into();
+        let root = get_commitment_root(&entries, TreeKind::Sha256(LeafEncoding::Packed));
+        assert_eq!(root, expected);
+        assert_ne!(
+            root,
+            get_commitment_root(&entries, TreeKind::Positional(LeafEncoding::Packed))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected one amount per winner")]
+    fn test_get_claim_leaves_amounts_mismatch() {
+        get_claim_leaves(&[[1; 20], [2; 20]], &[100]);
+    }
+
+    #[test]
+    fn test_openzeppelin_roots() {
+        let entries = entries();
+        let leaves: Vec<[u8; 32]> = entries
+            .iter()
+            .map(|entry| standard::hash_leaf(&entry_leaf(entry)))
+            .collect();
+        let root = get_commitment_root(&entries, TreeKind::OpenZeppelin);
+        assert_eq!(root, StandardMerkleTree::from_leaves(&leaves).root());
+        for (leaf, proof) in leaves
+            .iter()
+            .zip(TreeKind::OpenZeppelin.proofs(&leaves).unwrap())
+        {
+            assert!(standard::verify(&root, leaf, &proof));
+        }
+
+        let winners = draw(2, &[], [7; 32], &entries);
+        assert_eq!(
+            get_winners_root(2, &[], [7; 32], &entries, TreeKind::OpenZeppelin),
+            StandardMerkleTree::from_leaves(&[
+                standard::hash_leaf(&[LeafField::Address(winners[0])]),
+                standard::hash_leaf(&[LeafField::Address(winners[1])]),
+            ])
+            .root()
+        );
+        assert_ne!(
+            root,
+            get_commitment_root(&entries, TreeKind::Positional(LeafEncoding::Packed))
+        );
+    }
 }
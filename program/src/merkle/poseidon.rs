@@ -0,0 +1,155 @@
+// Positional tree hashed with Poseidon over the scalar field of BN254, with the parameters of
+// circomlib (and circomlibjs), so that roots and proofs can be checked cheaply inside
+// Circom/Noir circuits:
+// - leaves are Poseidon(values), every value being one field element (the 32-byte words of
+//   abi.encode(values))
+// - internal nodes are Poseidon(left, right), odd nodes are promoted to the next level
+// Hashes are the big-endian bytes of field elements
+use super::leaf::{abi_encode, LeafField};
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use light_poseidon::{Poseidon, PoseidonBytesHasher};
+use rs_merkle::{Hasher, MerkleTree};
+use std::cell::RefCell;
+
+// Widest Poseidon instance of circomlib (13 elements of state)
+const MAX_INPUTS: usize = 12;
+
+thread_local! {
+    // Instance for each number of inputs, built on first use: building one derives its round
+    // constants and MDS matrix, which costs about as much as the permutation itself
+    static POSEIDON: RefCell<[Option<Poseidon<Fr>>; MAX_INPUTS]> = RefCell::default();
+}
+
+#[derive(Clone)]
+pub struct Poseidon254Algorithm;
+
+impl Hasher for Poseidon254Algorithm {
+    type Hash = [u8; 32];
+    // Poseidon of the 32-byte big-endian field elements the data is made of
+    fn hash(data: &[u8]) -> [u8; 32] {
+        assert!(
+            data.len() % 32 == 0 && (1..=MAX_INPUTS).contains(&(data.len() / 32)),
+            "expected 1 to {MAX_INPUTS} field elements"
+        );
+        let inputs: Vec<&[u8]> = data.chunks(32).collect();
+        POSEIDON.with_borrow_mut(|instances| {
+            instances[inputs.len() - 1]
+                .get_or_insert_with(|| {
+                    Poseidon::<Fr>::new_circom(inputs.len()).unwrap_or_else(|err| panic!("{err}"))
+                })
+                .hash_bytes_be(&inputs)
+                .unwrap_or_else(|err| panic!("{err}"))
+        })
+    }
+}
+
+// Whether the bytes are the canonical encoding of a field element, i.e. are below the modulus
+pub fn is_field_element(bytes: &[u8; 32]) -> bool {
+    Fr::from_be_bytes_mod_order(bytes)
+        .into_bigint()
+        .to_bytes_be()
+        == bytes
+}
+
+pub fn hash_leaf(fields: &[LeafField]) -> [u8; 32] {
+    Poseidon254Algorithm::hash(&abi_encode(fields))
+}
+
+pub fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Poseidon254Algorithm::concat_and_hash(left, Some(right))
+}
+
+pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let tree = MerkleTree::<Poseidon254Algorithm>::from_leaves(leaves);
+    tree.root().ok_or("failed to compute root").unwrap()
+}
+
+pub fn proofs(leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+    let tree = MerkleTree::<Poseidon254Algorithm>::from_leaves(leaves);
+    (0..leaves.len())
+        .map(|index| tree.proof(&[index]).proof_hashes().to_vec())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+    use light_poseidon::PoseidonHasher;
+
+    fn field(value: u64) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        out[24..].copy_from_slice(&value.to_be_bytes());
+        out
+    }
+
+    #[test]
+    fn test_hash_matches_circomlibjs() {
+        // poseidon([1]) and poseidon([1, 2]) from circomlibjs
+        assert_eq!(
+            hex(&Poseidon254Algorithm::hash(&field(1))),
+            "29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133"
+        );
+        assert_eq!(
+            hex(&hash_node(&field(1), &field(2))),
+            "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+        );
+        // The cached instances hash the same again
+        assert_eq!(
+            Poseidon254Algorithm::hash(&field(1)),
+            Poseidon::<Fr>::new_circom(1)
+                .unwrap()
+                .hash_bytes_be(&[&field(1)])
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_leaves_are_field_elements() {
+        // An entry leaf is Poseidon(address, start, end), the address being taken as an integer
+        let address = [0x11; 20];
+        let fields = [
+            LeafField::Address(address),
+            LeafField::Uint64(10),
+            LeafField::Uint64(25),
+        ];
+        let expected = Poseidon::<Fr>::new_circom(3)
+            .unwrap()
+            .hash(&[
+                Fr::from_be_bytes_mod_order(&address),
+                Fr::from(10u64),
+                Fr::from(25u64),
+            ])
+            .unwrap();
+        let leaf = hash_leaf(&fields);
+        assert_eq!(leaf.to_vec(), expected.into_bigint().to_bytes_be());
+        assert!(is_field_element(&leaf));
+    }
+
+    #[test]
+    fn test_root_promotes_odd_nodes() {
+        let leaves: Vec<[u8; 32]> = (1..=3).map(field).collect();
+        assert_eq!(
+            root(&leaves),
+            hash_node(&hash_node(&leaves[0], &leaves[1]), &leaves[2])
+        );
+    }
+
+    #[test]
+    fn test_is_field_element() {
+        assert!(is_field_element(&[0; 32]));
+        assert!(!is_field_element(&[0xff; 32]));
+        // The modulus itself is not a field element, the element below it is
+        let mut modulus: [u8; 32] = Fr::MODULUS.to_bytes_be().try_into().unwrap();
+        assert!(!is_field_element(&modulus));
+        modulus[31] -= 1;
+        assert!(is_field_element(&modulus));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 1 to 12 field elements")]
+    fn test_hash_rejects_partial_elements() {
+        Poseidon254Algorithm::hash(&[1; 33]);
+    }
+}
@@ -0,0 +1,427 @@
+use super::{
+    distributor::DistributorTree,
+    get_claim_leaves, get_commitment_leaves, get_winners_leaves, incremental,
+    leaf::LeafEncoding,
+    poseidon, register_entries,
+    sparse::{self, SparseMerkleTree, SparseProof},
+    standard::{self, MultiProof, StandardMerkleTree},
+    tagged, Keccak256Algorithm, Sha256Algorithm, TreeKind,
+};
+use crate::raffle::{
+    draw, draw_steps,
+    sublinear::{DrawHit, SublinearDraw},
+    Entry, Quota,
+};
+use rs_merkle::Hasher;
+use std::collections::HashSet;
+
+// Proof that the leaf of an address is part of a tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafProof {
+    pub address: [u8; 20],
+    // Position of the leaf in the list the tree was built from (entry or draw order)
+    pub index: u64,
+    pub leaf: [u8; 32],
+    // Sibling path from the leaf up to the root
+    pub proof: Vec<[u8; 32]>,
+}
+
+fn get_leaf_proofs(
+    addresses: &[[u8; 20]],
+    leaves: Vec<[u8; 32]>,
+    proofs: Vec<Vec<[u8; 32]>>,
+) -> Vec<LeafProof> {
+    addresses
+        .iter()
+        .zip(leaves.iter().zip(proofs))
+        .enumerate()
+        .map(|(index, (address, (leaf, proof)))| LeafProof {
+            address: *address,
+            index: index as u64,
+            leaf: *leaf,
+            proof,
+        })
+        .collect()
+}
+
+// Proofs of every entry against the root computed by `get_commitment_root`
+pub fn get_commitment_proofs(entries: &[Entry], kind: TreeKind) -> Vec<LeafProof> {
+    let addresses: Vec<[u8; 20]> = entries.iter().map(|entry| entry.address).collect();
+    let leaves = get_commitment_leaves(entries, kind);
+    let proofs = kind
+        .proofs(&leaves)
+        .expect("sparse trees can only commit the winners, not the entries");
+    get_leaf_proofs(&addresses, leaves, proofs)
+}
+
+// Proofs of every winner against the root computed by `get_winners_root`
+pub fn get_winners_proofs(
+    num_winners: u64,
+    quotas: &[Quota],
+    seed: [u8; 32],
+    entries: &[Entry],
+    kind: TreeKind,
+) -> Vec<LeafProof> {
+    let winners = draw(num_winners, quotas, seed, entries);
+    let leaves = get_winners_leaves(&winners, kind);
+    match kind.proofs(&leaves) {
+        Some(proofs) => get_leaf_proofs(&winners, leaves, proofs),
+        // Uncompressed paths of the sparse tree, see `get_sparse_winners_proof` for compressed
+        // (non-)membership proofs
+        None => {
+            let tree = SparseMerkleTree::new(&winners, &leaves);
+            winners
+                .iter()
+                .zip(leaves)
+                .enumerate()
+                .map(|(index, (address, leaf))| LeafProof {
+                    address: *address,
+                    index: index as u64,
+                    leaf,
+                    proof: tree.prove(address).path(),
+                })
+                .collect()
+        }
+    }
+}
+
+// Membership proof of a winner, or non-membership proof of any other address, against the root
+// computed by `get_winners_root` with a sparse tree
+pub fn get_sparse_winners_proof(
+    address: &[u8; 20],
+    num_winners: u64,
+    quotas: &[Quota],
+    seed: [u8; 32],
+    entries: &[Entry],
+    encoding: LeafEncoding,
+) -> SparseProof {
+    let kind = TreeKind::Sparse(encoding);
+    let winners = draw(num_winners, quotas, seed, entries);
+    let leaves = get_winners_leaves(&winners, kind);
+    SparseMerkleTree::new(&winners, &leaves).prove(address)
+}
+
+// Proofs of every claim against the root computed by `get_claim_root`, to be checked with
+// `distributor::verify`
+pub fn get_claim_proofs(winners: &[[u8; 20]], amounts: &[u128]) -> Vec<LeafProof> {
+    let leaves = get_claim_leaves(winners, amounts);
+    let tree = DistributorTree::from_leaves(&leaves);
+    winners
+        .iter()
+        .zip(leaves)
+        .enumerate()
+        .map(|(index, (address, leaf))| LeafProof {
+            address: *address,
+            index: index as u64,
+            leaf,
+            proof: tree.proof(&leaf),
+        })
+        .collect()
+}
+
+// Input of `draw_sublinear`: the entry hit at each index of the permutation walked by the draw, with
+// its proof against the root computed by `get_commitment_root`
+pub fn get_sublinear_draw(
+    num_winners: u64,
+    seed: [u8; 32],
+    entries: &[Entry],
+    kind: TreeKind,
+) -> SublinearDraw {
+    let proofs = get_commitment_proofs(entries, kind);
+    let mut proven = HashSet::new();
+    let hits = draw_steps(num_winners, &[], seed, entries)
+        .into_iter()
+        .map(|step| {
+            let index = entries.partition_point(|entry| entry.end <= step.ticket);
+            if !proven.insert(index) {
+                return DrawHit::Repeat(index as u64);
+            }
+            DrawHit::Proven {
+                entry: step.entry.clone(),
+                index: index as u64,
+                proof: proofs[index].proof.clone(),
+            }
+        })
+        .collect();
+    let registration = register_entries(entries, kind);
+    SublinearDraw {
+        commitment_root: registration.commitment_root,
+        total_weight: registration.total_weight,
+        entry_count: registration.entry_count,
+        hits,
+    }
+}
+
+fn get_multi_proof(leaves: &[[u8; 32]], indices: &[usize], kind: TreeKind) -> MultiProof {
+    assert!(
+        kind == TreeKind::OpenZeppelin,
+        "multiproofs require OpenZeppelin trees"
+    );
+    StandardMerkleTree::from_leaves(leaves).multi_proof(indices)
+}
+
+// Multiproof of the entries at `indices` against the root computed by `get_commitment_root`
+pub fn get_commitment_multi_proof(
+    entries: &[Entry],
+    indices: &[usize],
+    kind: TreeKind,
+) -> MultiProof {
+    get_multi_proof(&get_commitment_leaves(entries, kind), indices, kind)
+}
+
+// Multiproof of the winners at `indices` (in draw order) against the root computed by
+// `get_winners_root`
+pub fn get_winners_multi_proof(
+    num_winners: u64,
+    quotas: &[Quota],
+    seed: [u8; 32],
+    entries: &[Entry],
+    indices: &[usize],
+    kind: TreeKind,
+) -> MultiProof {
+    let winners = draw(num_winners, quotas, seed, entries);
+    get_multi_proof(&get_winners_leaves(&winners, kind), indices, kind)
+}
+
+// Check a proof against the root of a tree of `num_leaves` leaves
+// The index and number of leaves are only needed by positional (keccak256, SHA-256 or Poseidon)
+// and tagged trees, where they determine the side of each sibling and the levels at which the node
+// was promoted (incremental trees only need the index, sparse trees neither)
+pub fn verify_proof(root: &[u8; 32], proof: &LeafProof, num_leaves: u64, kind: TreeKind) -> bool {
+    match kind {
+        TreeKind::Positional(_) => {
+            fold_positional(proof, num_leaves, |left, right| {
+                Keccak256Algorithm::concat_and_hash(left, Some(right))
+            }) == Some(*root)
+        }
+        TreeKind::Sha256(_) => {
+            fold_positional(proof, num_leaves, |left, right| {
+                Sha256Algorithm::concat_and_hash(left, Some(right))
+            }) == Some(*root)
+        }
+        TreeKind::OpenZeppelin => standard::verify(root, &proof.leaf, &proof.proof),
+        TreeKind::Incremental(_) => {
+            incremental::verify(root, &proof.leaf, proof.index, &proof.proof)
+        }
+        TreeKind::Sparse(_) => sparse::verify_path(
+            root,
+            &sparse::key(&proof.address),
+            &proof.leaf,
+            &proof.proof,
+        ),
+        TreeKind::Tagged(_) => fold_positional(proof, num_leaves, tagged::hash_node)
+            .is_some_and(|tree_root| tagged::hash_root(num_leaves, &tree_root) == *root),
+        // Poseidon only hashes field elements
+        TreeKind::Poseidon => {
+            std::iter::once(&proof.leaf)
+                .chain(&proof.proof)
+                .all(poseidon::is_field_element)
+                && fold_positional(proof, num_leaves, poseidon::hash_node) == Some(*root)
+        }
+    }
+}
+
+// Root of a positional tree rebuilt from the proof, or None if the proof does not have the shape
+// of a path of this tree
+fn fold_positional(
+    proof: &LeafProof,
+    num_leaves: u64,
+    hash_node: impl Fn(&[u8; 32], &[u8; 32]) -> [u8; 32],
+) -> Option<[u8; 32]> {
+    if proof.index >= num_leaves {
+        return None;
+    }
+    let mut siblings = proof.proof.iter();
+    let mut node = proof.leaf;
+    let (mut index, mut width) = (proof.index, num_leaves);
+    while width > 1 {
+        if index % 2 == 1 {
+            node = hash_node(siblings.next()?, &node);
+        } else if index + 1 < width {
+            node = hash_node(&node, siblings.next()?);
+        }
+        index /= 2;
+        width = width.div_ceil(2);
+    }
+    siblings.next().is_none().then_some(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::{
+        distributor, get_claim_root, get_commitment_root, get_winners_root, leaf::winner_leaf,
+    };
+    use crate::test_util::entries;
+
+    const KINDS: [TreeKind; 8] = [
+        TreeKind::Positional(LeafEncoding::Packed),
+        TreeKind::Positional(LeafEncoding::Abi),
+        TreeKind::OpenZeppelin,
+        TreeKind::Tagged(LeafEncoding::Packed),
+        TreeKind::Tagged(LeafEncoding::Abi),
+        TreeKind::Incremental(LeafEncoding::Packed),
+        TreeKind::Poseidon,
+        TreeKind::Sha256(LeafEncoding::Abi),
+    ];
+
+    #[test]
+    fn test_commitment_proofs() {
+        for kind in KINDS {
+            for n in 2..=17 {
+                let entries = entries(&vec![10; n as usize]);
+                let root = get_commitment_root(&entries, kind);
+                let proofs = get_commitment_proofs(&entries, kind);
+                for (entry, proof) in entries.iter().zip(&proofs) {
+                    assert_eq!(proof.address, entry.address);
+                    assert!(verify_proof(&root, proof, n, kind), "invalid proof");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_winners_proofs() {
+        let entries = entries(&[10; 50]);
+        for kind in KINDS {
+            for num_winners in [1, 2, 7, 50] {
+                let root = get_winners_root(num_winners, &[], [7; 32], &entries, kind);
+                let winners = draw(num_winners, &[], [7; 32], &entries);
+                let proofs = get_winners_proofs(num_winners, &[], [7; 32], &entries, kind);
+                for (i, proof) in proofs.iter().enumerate() {
+                    assert_eq!(proof.address, winners[i]);
+                    assert_eq!(proof.index, i as u64);
+                    assert!(
+                        verify_proof(&root, proof, num_winners, kind),
+                        "invalid proof"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sparse_winners_proofs() {
+        let entries = entries(&[10; 20]);
+        let kind = TreeKind::Sparse(LeafEncoding::Abi);
+        let root = get_winners_root(5, &[], [7; 32], &entries, kind);
+        let winners = draw(5, &[], [7; 32], &entries);
+        for proof in get_winners_proofs(5, &[], [7; 32], &entries, kind) {
+            assert!(verify_proof(&root, &proof, 5, kind), "invalid proof");
+        }
+        for entry in &entries {
+            let proof = get_sparse_winners_proof(
+                &entry.address,
+                5,
+                &[],
+                [7; 32],
+                &entries,
+                LeafEncoding::Abi,
+            );
+            assert!(sparse::verify(&root, &proof));
+            assert_eq!(proof.is_member(), winners.contains(&entry.address));
+        }
+    }
+
+    #[test]
+    fn test_multi_proofs() {
+        let entries = entries(&[10; 20]);
+        let kind = TreeKind::OpenZeppelin;
+        let root = get_commitment_root(&entries, kind);
+        let multi_proof = get_commitment_multi_proof(&entries, &[0, 3, 4, 19], kind);
+        assert!(standard::verify_multi_proof(&root, &multi_proof));
+
+        let root = get_winners_root(6, &[], [7; 32], &entries, kind);
+        let winners = draw(6, &[], [7; 32], &entries);
+        let multi_proof = get_winners_multi_proof(6, &[], [7; 32], &entries, &[1, 2, 5], kind);
+        assert!(standard::verify_multi_proof(&root, &multi_proof));
+        for (index, leaf) in multi_proof.indices.iter().zip(&multi_proof.leaves) {
+            assert_eq!(*leaf, kind.hash_leaf(&winner_leaf(&winners[*index])));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "multiproofs require OpenZeppelin trees")]
+    fn test_multi_proofs_positional() {
+        get_commitment_multi_proof(
+            &entries(&[10; 4]),
+            &[0, 1],
+            TreeKind::Positional(LeafEncoding::Packed),
+        );
+    }
+
+    #[test]
+    fn test_claim_proofs() {
+        let winners = draw(5, &[], [7; 32], &entries(&[10; 20]));
+        let amounts = [1000, 500, 500, 100, 100];
+        let root = get_claim_root(&winners, &amounts);
+        for (i, proof) in get_claim_proofs(&winners, &amounts).iter().enumerate() {
+            assert_eq!(proof.address, winners[i]);
+            assert_eq!(proof.index, i as u64);
+            assert_eq!(
+                proof.leaf,
+                distributor::hash_claim(i as u64, &winners[i], amounts[i])
+            );
+            assert!(
+                distributor::verify(&root, &proof.leaf, &proof.proof),
+                "invalid proof"
+            );
+        }
+        // The amount is bound to the leaf
+        assert_ne!(root, get_claim_root(&winners, &[1000, 500, 500, 100, 101]));
+    }
+
+    #[test]
+    fn test_verify_proof_rejects_tampering() {
+        let entries = entries(&[10; 11]);
+        for kind in KINDS {
+            let root = get_commitment_root(&entries, kind);
+            let proof = get_commitment_proofs(&entries, kind).remove(6);
+
+            let mut wrong_leaf = proof.clone();
+            wrong_leaf.leaf[0] ^= 1;
+            assert!(!verify_proof(&root, &wrong_leaf, 11, kind));
+
+            let mut wrong_sibling = proof.clone();
+            wrong_sibling.proof[1][0] ^= 1;
+            assert!(!verify_proof(&root, &wrong_sibling, 11, kind));
+
+            let mut truncated = proof.clone();
+            truncated.proof.pop();
+            assert!(!verify_proof(&root, &truncated, 11, kind));
+        }
+
+        // Positional (of any hash) and tagged proofs are bound to the index and number of leaves
+        // (the last of 11 leaves is promoted on the first level, the last of 12 is not)
+        for kind in [
+            TreeKind::Positional(LeafEncoding::Packed),
+            TreeKind::Tagged(LeafEncoding::Packed),
+            TreeKind::Poseidon,
+            TreeKind::Sha256(LeafEncoding::Packed),
+        ] {
+            let root = get_commitment_root(&entries, kind);
+            let mut proof = get_commitment_proofs(&entries, kind).remove(10);
+            assert!(verify_proof(&root, &proof, 11, kind));
+            assert!(!verify_proof(&root, &proof, 12, kind));
+            proof.index = 9;
+            assert!(!verify_proof(&root, &proof, 11, kind));
+        }
+
+        // Tagged proofs are bound to the number of leaves even where the path is the same
+        let root = get_commitment_root(&entries, TreeKind::Tagged(LeafEncoding::Packed));
+        let proof =
+            get_commitment_proofs(&entries, TreeKind::Tagged(LeafEncoding::Packed)).remove(6);
+        assert!(verify_proof(
+            &root,
+            &proof,
+            11,
+            TreeKind::Tagged(LeafEncoding::Packed)
+        ));
+        assert!(!verify_proof(
+            &root,
+            &proof,
+            12,
+            TreeKind::Tagged(LeafEncoding::Packed)
+        ));
+    }
+}
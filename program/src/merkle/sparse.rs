@@ -0,0 +1,318 @@
+// Sparse Merkle tree of depth 256 keyed by address, which can prove that an address is absent:
+// - the key of an address is H(address), read from its most significant bit at the root down to
+//   its least significant bit at the leaves
+// - present addresses hold their (non-zero) hashed leaf, every other position holds bytes32(0)
+// - default_hashes[0] = bytes32(0), default_hashes[h + 1] = H(default_hashes[h] || default_hashes[h])
+//   is the root of an empty subtree of height h
+// - internal nodes are H(left || right)
+use super::Keccak256Algorithm;
+use rs_merkle::Hasher;
+
+pub const DEPTH: usize = 256;
+
+pub fn key(address: &[u8; 20]) -> [u8; 32] {
+    Keccak256Algorithm::hash(address)
+}
+
+// Bit of the key selecting the child at the given height (1 selects the right child)
+fn bit(key: &[u8; 32], height: usize) -> bool {
+    (key[31 - height / 8] >> (height % 8)) & 1 == 1
+}
+
+pub fn default_hashes() -> Vec<[u8; 32]> {
+    let mut default_hashes = vec![[0u8; 32]];
+    for h in 0..DEPTH {
+        default_hashes.push(Keccak256Algorithm::concat_and_hash(
+            &default_hashes[h],
+            Some(&default_hashes[h]),
+        ));
+    }
+    default_hashes
+}
+
+// Proof of the value held at a key: the leaf for members, bytes32(0) for non-members
+// Siblings are compressed: bit h of `bitmap` (a big-endian uint256) is set when the sibling at
+// height h is not the default hash, and only those siblings are listed, from the leaf up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseProof {
+    pub key: [u8; 32],
+    pub leaf: [u8; 32],
+    pub bitmap: [u8; 32],
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl SparseProof {
+    pub fn is_member(&self) -> bool {
+        self.leaf != [0u8; 32]
+    }
+
+    // Uncompressed sibling path, DEPTH hashes from the leaf up
+    pub fn path(&self) -> Vec<[u8; 32]> {
+        let default_hashes = default_hashes();
+        let mut siblings = self.siblings.iter();
+        (0..DEPTH)
+            .map(|h| match bit(&self.bitmap, h) {
+                true => *siblings.next().expect("missing sibling"),
+                false => default_hashes[h],
+            })
+            .collect()
+    }
+}
+
+pub struct SparseMerkleTree {
+    // (key, leaf) sorted by key
+    leaves: Vec<([u8; 32], [u8; 32])>,
+    default_hashes: Vec<[u8; 32]>,
+}
+
+impl SparseMerkleTree {
+    pub fn new(addresses: &[[u8; 20]], leaves: &[[u8; 32]]) -> Self {
+        assert!(
+            addresses.len() == leaves.len(),
+            "expected one leaf per address"
+        );
+        let mut keyed: Vec<([u8; 32], [u8; 32])> = addresses
+            .iter()
+            .zip(leaves)
+            .map(|(address, leaf)| {
+                assert!(*leaf != [0u8; 32], "empty leaf");
+                (key(address), *leaf)
+            })
+            .collect();
+        keyed.sort();
+        assert!(
+            keyed.windows(2).all(|pair| pair[0].0 != pair[1].0),
+            "duplicate address"
+        );
+        SparseMerkleTree {
+            leaves: keyed,
+            default_hashes: default_hashes(),
+        }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.subtree_root(&self.leaves, DEPTH)
+    }
+
+    // Root of the subtree of the given height holding `leaves`
+    fn subtree_root(&self, leaves: &[([u8; 32], [u8; 32])], height: usize) -> [u8; 32] {
+        if leaves.is_empty() {
+            return self.default_hashes[height];
+        }
+        if height == 0 {
+            return leaves[0].1;
+        }
+        let split = leaves.partition_point(|(key, _)| !bit(key, height - 1));
+        Keccak256Algorithm::concat_and_hash(
+            &self.subtree_root(&leaves[..split], height - 1),
+            Some(&self.subtree_root(&leaves[split..], height - 1)),
+        )
+    }
+
+    // Membership proof if the address is in the tree, non-membership proof otherwise
+    pub fn prove(&self, address: &[u8; 20]) -> SparseProof {
+        let key = key(address);
+        let mut bitmap = [0u8; 32];
+        let mut siblings = vec![];
+        let mut leaves = &self.leaves[..];
+        for height in (1..=DEPTH).rev() {
+            let split = leaves.partition_point(|(key, _)| !bit(key, height - 1));
+            let (path, other) = match bit(&key, height - 1) {
+                true => (&leaves[split..], &leaves[..split]),
+                false => (&leaves[..split], &leaves[split..]),
+            };
+            if !other.is_empty() {
+                bitmap[31 - (height - 1) / 8] |= 1 << ((height - 1) % 8);
+                siblings.push(self.subtree_root(other, height - 1));
+            }
+            leaves = path;
+        }
+        siblings.reverse();
+        SparseProof {
+            key,
+            leaf: leaves.first().map_or([0u8; 32], |(_, leaf)| *leaf),
+            bitmap,
+            siblings,
+        }
+    }
+}
+
+// Check the value held at the proof's key
+pub fn verify(root: &[u8; 32], proof: &SparseProof) -> bool {
+    if proof.siblings.len() != (0..DEPTH).filter(|h| bit(&proof.bitmap, *h)).count() {
+        return false;
+    }
+    verify_path(root, &proof.key, &proof.leaf, &proof.path())
+}
+
+// Check an uncompressed sibling path (see `SparseProof::path`)
+pub fn verify_path(root: &[u8; 32], key: &[u8; 32], leaf: &[u8; 32], path: &[[u8; 32]]) -> bool {
+    if path.len() != DEPTH {
+        return false;
+    }
+    let computed = path.iter().enumerate().fold(*leaf, |node, (h, sibling)| {
+        if bit(key, h) {
+            Keccak256Algorithm::concat_and_hash(sibling, Some(&node))
+        } else {
+            Keccak256Algorithm::concat_and_hash(&node, Some(sibling))
+        }
+    });
+    computed == *root
+}
+
+pub fn verify_membership(
+    root: &[u8; 32],
+    address: &[u8; 20],
+    leaf: &[u8; 32],
+    proof: &SparseProof,
+) -> bool {
+    proof.key == key(address) && proof.leaf == *leaf && *leaf != [0u8; 32] && verify(root, proof)
+}
+
+pub fn verify_non_membership(root: &[u8; 32], address: &[u8; 20], proof: &SparseProof) -> bool {
+    proof.key == key(address) && !proof.is_member() && verify(root, proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(n: u8) -> (Vec<[u8; 20]>, Vec<[u8; 32]>, SparseMerkleTree) {
+        let addresses: Vec<[u8; 20]> = (1..=n).map(|i| [i; 20]).collect();
+        let leaves: Vec<[u8; 32]> = addresses
+            .iter()
+            .map(|address| Keccak256Algorithm::hash(address))
+            .collect();
+        let tree = SparseMerkleTree::new(&addresses, &leaves);
+        (addresses, leaves, tree)
+    }
+
+    // Root computed by inserting the leaves one by one along their full paths
+    fn naive_root(addresses: &[[u8; 20]], leaves: &[[u8; 32]]) -> [u8; 32] {
+        use std::collections::HashMap;
+        let default_hashes = default_hashes();
+        // Nodes by (height, key prefix of DEPTH - height bits, as a key with the other bits cleared)
+        let mut nodes: HashMap<(usize, [u8; 32]), [u8; 32]> = HashMap::new();
+        let prefix = |key: &[u8; 32], height: usize| {
+            let mut prefix = *key;
+            for h in 0..height {
+                prefix[31 - h / 8] &= !(1 << (h % 8));
+            }
+            prefix
+        };
+        for (address, leaf) in addresses.iter().zip(leaves) {
+            let key = key(address);
+            nodes.insert((0, key), *leaf);
+            for height in 1..=DEPTH {
+                let left = prefix(&key, height);
+                let mut right = left;
+                right[31 - (height - 1) / 8] |= 1 << ((height - 1) % 8);
+                let child = |key| {
+                    *nodes
+                        .get(&(height - 1, key))
+                        .unwrap_or(&default_hashes[height - 1])
+                };
+                let node = Keccak256Algorithm::concat_and_hash(&child(left), Some(&child(right)));
+                nodes.insert((height, prefix(&key, height)), node);
+            }
+        }
+        *nodes
+            .get(&(DEPTH, [0u8; 32]))
+            .unwrap_or(&default_hashes[DEPTH])
+    }
+
+    #[test]
+    fn test_root_matches_naive_insertion() {
+        assert_eq!(tree(0).2.root(), default_hashes()[DEPTH]);
+        for n in [1, 2, 5] {
+            let (addresses, leaves, tree) = tree(n);
+            assert_eq!(tree.root(), naive_root(&addresses, &leaves));
+        }
+    }
+
+    #[test]
+    fn test_root_ignores_order() {
+        let (mut addresses, mut leaves, tree) = tree(6);
+        addresses.reverse();
+        leaves.reverse();
+        assert_eq!(
+            SparseMerkleTree::new(&addresses, &leaves).root(),
+            tree.root()
+        );
+    }
+
+    #[test]
+    fn test_membership_proofs() {
+        let (addresses, leaves, tree) = tree(7);
+        let root = tree.root();
+        for (address, leaf) in addresses.iter().zip(&leaves) {
+            let proof = tree.prove(address);
+            assert!(proof.is_member());
+            assert!(verify_membership(&root, address, leaf, &proof));
+            assert!(!verify_non_membership(&root, address, &proof));
+            // Only the siblings of non-empty subtrees are listed
+            assert!(proof.siblings.len() < 8);
+        }
+    }
+
+    #[test]
+    fn test_non_membership_proofs() {
+        let (addresses, leaves, tree) = tree(7);
+        let root = tree.root();
+        for i in 8..20u8 {
+            let address = [i; 20];
+            let proof = tree.prove(&address);
+            assert!(!proof.is_member());
+            assert!(verify_non_membership(&root, &address, &proof));
+            assert!(!verify_membership(&root, &address, &leaves[0], &proof));
+        }
+
+        // A member cannot be proven absent with a forged zero leaf
+        let mut forged = tree.prove(&addresses[0]);
+        forged.leaf = [0u8; 32];
+        assert!(!verify_non_membership(&root, &addresses[0], &forged));
+        // Nor with the non-membership proof of another address
+        let other = tree.prove(&[8; 20]);
+        assert!(!verify_non_membership(&root, &addresses[0], &other));
+    }
+
+    #[test]
+    fn test_verify_rejects_tampering() {
+        let (addresses, leaves, tree) = tree(4);
+        let root = tree.root();
+        let proof = tree.prove(&addresses[2]);
+
+        let mut wrong_sibling = proof.clone();
+        wrong_sibling.siblings[0][0] ^= 1;
+        assert!(!verify_membership(
+            &root,
+            &addresses[2],
+            &leaves[2],
+            &wrong_sibling
+        ));
+
+        let mut wrong_bitmap = proof.clone();
+        wrong_bitmap.bitmap[0] ^= 0x80;
+        assert!(!verify_membership(
+            &root,
+            &addresses[2],
+            &leaves[2],
+            &wrong_bitmap
+        ));
+
+        let mut truncated = proof.clone();
+        truncated.siblings.pop();
+        assert!(!verify_membership(
+            &root,
+            &addresses[2],
+            &leaves[2],
+            &truncated
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate address")]
+    fn test_new_duplicate_address() {
+        SparseMerkleTree::new(&[[1; 20], [1; 20]], &[[1; 32], [2; 32]]);
+    }
+}
@@ -0,0 +1,315 @@
+// Merkle tree compatible with OpenZeppelin's StandardMerkleTree (@openzeppelin/merkle-tree), so
+// that roots and proofs can be checked with MerkleProof.verify:
+// - leaves are H(H(abi.encode(values)))
+// - leaves are sorted before building the tree
+// - the tree is a complete binary tree stored as an array, with leaves at the end in reverse
+// - internal nodes are H(min(a, b) || max(a, b))
+use super::{leaf::abi_encode, leaf::LeafField, Keccak256Algorithm};
+use rs_merkle::Hasher;
+use std::collections::VecDeque;
+
+pub fn hash_leaf(fields: &[LeafField]) -> [u8; 32] {
+    Keccak256Algorithm::hash(&Keccak256Algorithm::hash(&abi_encode(fields)))
+}
+
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    Keccak256Algorithm::concat_and_hash(left, Some(right))
+}
+
+pub struct StandardMerkleTree {
+    tree: Vec<[u8; 32]>,
+    // Position in `tree` of each leaf, in the order the leaves were given
+    tree_indices: Vec<usize>,
+}
+
+impl StandardMerkleTree {
+    // Build the tree from hashed leaves (see `hash_leaf`)
+    pub fn from_leaves(leaves: &[[u8; 32]]) -> Self {
+        assert!(!leaves.is_empty(), "expected non-zero number of leaves");
+
+        let mut order: Vec<usize> = (0..leaves.len()).collect();
+        order.sort_by(|a, b| leaves[*a].cmp(&leaves[*b]));
+
+        let len = 2 * leaves.len() - 1;
+        let mut tree = vec![[0u8; 32]; len];
+        let mut tree_indices = vec![0; leaves.len()];
+        for (i, leaf_index) in order.into_iter().enumerate() {
+            tree[len - 1 - i] = leaves[leaf_index];
+            tree_indices[leaf_index] = len - 1 - i;
+        }
+        for i in (0..len - leaves.len()).rev() {
+            tree[i] = hash_pair(&tree[2 * i + 1], &tree[2 * i + 2]);
+        }
+
+        StandardMerkleTree { tree, tree_indices }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.tree[0]
+    }
+
+    // Sibling path of the leaf given at position `index`, from the leaf up to the root
+    pub fn proof(&self, index: usize) -> Vec<[u8; 32]> {
+        let mut i = self.tree_indices[index];
+        let mut proof = vec![];
+        while i > 0 {
+            let sibling = if i % 2 == 1 { i + 1 } else { i - 1 };
+            proof.push(self.tree[sibling]);
+            i = (i - 1) / 2;
+        }
+        proof
+    }
+
+    // Multiproof of the leaves given at positions `indices`, equivalent of getMultiProof
+    pub fn multi_proof(&self, indices: &[usize]) -> MultiProof {
+        let mut tree_indices: Vec<usize> = indices
+            .iter()
+            .map(|index| self.tree_indices[*index])
+            .collect();
+        tree_indices.sort_by(|a, b| b.cmp(a));
+        assert!(
+            tree_indices.windows(2).all(|pair| pair[0] != pair[1]),
+            "cannot prove duplicated index"
+        );
+
+        let mut stack: VecDeque<usize> = tree_indices.iter().copied().collect();
+        let (mut proof, mut proof_flags) = (vec![], vec![]);
+        while let Some(&j) = stack.front().filter(|j| **j > 0) {
+            stack.pop_front();
+            let sibling = if j % 2 == 1 { j + 1 } else { j - 1 };
+            if stack.front() == Some(&sibling) {
+                proof_flags.push(true);
+                stack.pop_front();
+            } else {
+                proof_flags.push(false);
+                proof.push(self.tree[sibling]);
+            }
+            stack.push_back((j - 1) / 2);
+        }
+        if tree_indices.is_empty() {
+            proof.push(self.tree[0]);
+        }
+
+        MultiProof {
+            indices: tree_indices
+                .iter()
+                .map(|i| self.tree_indices.iter().position(|j| j == i).unwrap())
+                .collect(),
+            leaves: tree_indices.iter().map(|i| self.tree[*i]).collect(),
+            proof,
+            proof_flags,
+        }
+    }
+}
+
+// Proof of several leaves at once, in the format of MerkleProof.multiProofVerify
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiProof {
+    // Position of each leaf in the order the leaves were given to the tree
+    pub indices: Vec<usize>,
+    pub leaves: Vec<[u8; 32]>,
+    pub proof: Vec<[u8; 32]>,
+    pub proof_flags: Vec<bool>,
+}
+
+// Equivalent of MerkleProof.verify(proof, root, leaf)
+pub fn verify(root: &[u8; 32], leaf: &[u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(*leaf, |node, sibling| hash_pair(&node, sibling));
+    computed == *root
+}
+
+// Equivalent of MerkleProof.multiProofVerify(proof, proofFlags, root, leaves), returning false
+// where processMultiProof reverts
+pub fn verify_multi_proof(root: &[u8; 32], multi_proof: &MultiProof) -> bool {
+    let MultiProof {
+        leaves,
+        proof,
+        proof_flags,
+        ..
+    } = multi_proof;
+    if leaves.len() + proof.len() != proof_flags.len() + 1 {
+        return false;
+    }
+
+    let mut hashes: Vec<[u8; 32]> = Vec::with_capacity(proof_flags.len());
+    let (mut leaf_pos, mut hash_pos, mut proof_pos) = (0, 0, 0);
+    // Next leaf, then next computed hash
+    let mut next = |hashes: &Vec<[u8; 32]>| -> Option<[u8; 32]> {
+        if leaf_pos < leaves.len() {
+            leaf_pos += 1;
+            Some(leaves[leaf_pos - 1])
+        } else {
+            hash_pos += 1;
+            hashes.get(hash_pos - 1).copied()
+        }
+    };
+    for flag in proof_flags {
+        let Some(a) = next(&hashes) else {
+            return false;
+        };
+        let b = if *flag {
+            next(&hashes)
+        } else {
+            proof_pos += 1;
+            proof.get(proof_pos - 1).copied()
+        };
+        let Some(b) = b else {
+            return false;
+        };
+        hashes.push(hash_pair(&a, &b));
+    }
+
+    let computed = match (hashes.last(), leaves.first()) {
+        (Some(hash), _) if proof_pos == proof.len() => *hash,
+        (Some(_), _) => return false,
+        (None, Some(leaf)) => *leaf,
+        (None, None) => proof[0],
+    };
+    computed == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::from_hex;
+
+    #[test]
+    fn test_standard_merkle_tree_matches_openzeppelin() {
+        // Example from the @openzeppelin/merkle-tree README:
+        // StandardMerkleTree.of([
+        //   ["0x1111111111111111111111111111111111111111", "5000000000000000000"],
+        //   ["0x2222222222222222222222222222222222222222", "2500000000000000000"]
+        // ], ["address", "uint256"]).root
+        let leaves = [
+            hash_leaf(&[
+                LeafField::Address([0x11; 20]),
+                LeafField::Uint64(5_000_000_000_000_000_000),
+            ]),
+            hash_leaf(&[
+                LeafField::Address([0x22; 20]),
+                LeafField::Uint64(2_500_000_000_000_000_000),
+            ]),
+        ];
+        let tree = StandardMerkleTree::from_leaves(&leaves);
+        assert_eq!(
+            tree.root(),
+            from_hex("0xd4dee0beab2d53f2cc83e567171bd2820e49898130a22622b10ead383e90bd77")
+        );
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert!(verify(&tree.root(), leaf, &tree.proof(i)));
+        }
+    }
+
+    #[test]
+    fn test_standard_merkle_tree_proofs() {
+        for num_leaves in 1..=17u8 {
+            let leaves: Vec<[u8; 32]> = (0..num_leaves)
+                .map(|i| hash_leaf(&[LeafField::Address([i; 20])]))
+                .collect();
+            let tree = StandardMerkleTree::from_leaves(&leaves);
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = tree.proof(i);
+                assert!(verify(&tree.root(), leaf, &proof), "invalid proof");
+                assert!(!verify(&tree.root(), &[0; 32], &proof), "forged proof");
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_proofs() {
+        for num_leaves in 1..=9u8 {
+            let leaves: Vec<[u8; 32]> = (0..num_leaves)
+                .map(|i| hash_leaf(&[LeafField::Address([i; 20])]))
+                .collect();
+            let tree = StandardMerkleTree::from_leaves(&leaves);
+            let root = tree.root();
+            // Every subset of the leaves
+            for subset in 0..1u32 << num_leaves {
+                let indices: Vec<usize> = (0..num_leaves as usize)
+                    .filter(|i| subset >> i & 1 == 1)
+                    .collect();
+                let multi_proof = tree.multi_proof(&indices);
+                assert!(
+                    verify_multi_proof(&root, &multi_proof),
+                    "invalid multiproof"
+                );
+                let mut sorted = multi_proof.indices.clone();
+                sorted.sort();
+                assert_eq!(sorted, indices);
+                for (index, leaf) in multi_proof.indices.iter().zip(&multi_proof.leaves) {
+                    assert_eq!(*leaf, leaves[*index]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_proof_matches_openzeppelin() {
+        // StandardMerkleTree.of(<values of the README example>, ["address", "uint256"])
+        //   .getMultiProof([0, 1])
+        // proves both leaves with no proof hash and a single flag
+        let leaves = [
+            hash_leaf(&[
+                LeafField::Address([0x11; 20]),
+                LeafField::Uint64(5_000_000_000_000_000_000),
+            ]),
+            hash_leaf(&[
+                LeafField::Address([0x22; 20]),
+                LeafField::Uint64(2_500_000_000_000_000_000),
+            ]),
+        ];
+        let tree = StandardMerkleTree::from_leaves(&leaves);
+        let multi_proof = tree.multi_proof(&[0, 1]);
+        assert!(multi_proof.proof.is_empty());
+        assert_eq!(multi_proof.proof_flags, vec![true]);
+        assert!(verify_multi_proof(&tree.root(), &multi_proof));
+
+        // A single leaf is proven by its sibling path
+        let multi_proof = tree.multi_proof(&[1]);
+        assert_eq!(multi_proof.proof, tree.proof(1));
+        assert_eq!(multi_proof.proof_flags, vec![false]);
+    }
+
+    #[test]
+    fn test_multi_proof_rejects_tampering() {
+        let leaves: Vec<[u8; 32]> = (0..7u8)
+            .map(|i| hash_leaf(&[LeafField::Address([i; 20])]))
+            .collect();
+        let tree = StandardMerkleTree::from_leaves(&leaves);
+        let root = tree.root();
+        let multi_proof = tree.multi_proof(&[1, 4, 5]);
+
+        let mut wrong_leaf = multi_proof.clone();
+        wrong_leaf.leaves[0][0] ^= 1;
+        assert!(!verify_multi_proof(&root, &wrong_leaf));
+
+        let mut wrong_flags = multi_proof.clone();
+        wrong_flags.proof_flags.reverse();
+        wrong_flags.proof_flags[0] = !wrong_flags.proof_flags[0];
+        assert!(!verify_multi_proof(&root, &wrong_flags));
+
+        let mut truncated = multi_proof.clone();
+        truncated.proof.pop();
+        assert!(!verify_multi_proof(&root, &truncated));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot prove duplicated index")]
+    fn test_multi_proof_duplicated_index() {
+        let leaves = [[1; 32], [2; 32]];
+        StandardMerkleTree::from_leaves(&leaves).multi_proof(&[1, 1]);
+    }
+
+    #[test]
+    fn test_standard_merkle_tree_ignores_leaf_order() {
+        let mut leaves: Vec<[u8; 32]> = (0..5u8)
+            .map(|i| hash_leaf(&[LeafField::Address([i; 20])]))
+            .collect();
+        let root = StandardMerkleTree::from_leaves(&leaves).root();
+        leaves.reverse();
+        assert_eq!(StandardMerkleTree::from_leaves(&leaves).root(), root);
+    }
+}
@@ -0,0 +1,86 @@
+// Merkle root of a stream of leaves, keeping only the roots of the complete subtrees built so far
+// (at most one per height, i.e. O(log n) nodes) instead of every level of the tree
+// Pairs are hashed as soon as both children are known, and the pending roots are folded from the
+// right at the end, which promotes odd nodes exactly like rs_merkle
+use rs_merkle::Hasher;
+use std::marker::PhantomData;
+
+pub struct StreamingRoot<H: Hasher<Hash = [u8; 32]>> {
+    // (height, root) of complete subtrees, by strictly decreasing height
+    stack: Vec<(u32, [u8; 32])>,
+    count: u64,
+    hasher: PhantomData<H>,
+}
+
+impl<H: Hasher<Hash = [u8; 32]>> Default for StreamingRoot<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: Hasher<Hash = [u8; 32]>> StreamingRoot<H> {
+    pub fn new() -> Self {
+        StreamingRoot {
+            stack: Vec::with_capacity(64),
+            count: 0,
+            hasher: PhantomData,
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn push(&mut self, leaf: [u8; 32]) {
+        self.count += 1;
+        let (mut height, mut node) = (0, leaf);
+        while let Some(&(left_height, left)) = self.stack.last() {
+            if left_height != height {
+                break;
+            }
+            self.stack.pop();
+            node = H::concat_and_hash(&left, Some(&node));
+            height += 1;
+        }
+        self.stack.push((height, node));
+    }
+
+    // None if no leaf was pushed
+    pub fn root(&self) -> Option<[u8; 32]> {
+        self.stack
+            .iter()
+            .rev()
+            .map(|(_, node)| *node)
+            .reduce(|right, left| H::concat_and_hash(&left, Some(&right)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::{get_merkle_root, tagged::TaggedKeccak256Algorithm, Keccak256Algorithm};
+    use rs_merkle::MerkleTree;
+
+    #[test]
+    fn test_streaming_root_matches_rs_merkle() {
+        for num_leaves in 1..=70u8 {
+            let leaves: Vec<[u8; 32]> = (0..num_leaves).map(|i| [i; 32]).collect();
+
+            let mut stream = StreamingRoot::<Keccak256Algorithm>::new();
+            let mut tagged_stream = StreamingRoot::<TaggedKeccak256Algorithm>::new();
+            for leaf in &leaves {
+                stream.push(*leaf);
+                tagged_stream.push(*leaf);
+            }
+            assert_eq!(stream.count(), num_leaves as u64);
+            assert_eq!(stream.root().unwrap(), get_merkle_root(leaves.clone()));
+            assert_eq!(
+                tagged_stream.root(),
+                MerkleTree::<TaggedKeccak256Algorithm>::from_leaves(&leaves).root()
+            );
+            // One pending root per set bit of the number of leaves
+            assert_eq!(stream.stack.len(), num_leaves.count_ones() as usize);
+        }
+        assert!(StreamingRoot::<Keccak256Algorithm>::new().root().is_none());
+    }
+}
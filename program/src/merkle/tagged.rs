@@ -0,0 +1,97 @@
+// Positional tree with domain-separated hashing that commits to its number of leaves:
+// - leaves are H(0x00 || encoding(values))
+// - internal nodes are H(0x01 || left || right), odd nodes are promoted to the next level
+// - the root is H(0x02 || uint64(number of leaves) || root of the tree)
+// so that a leaf can never be taken for an internal node (or the other way around), and trees of
+// different sizes cannot share a root
+use rs_merkle::{Hasher, MerkleTree};
+use sha3::{Digest, Keccak256};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+const ROOT_PREFIX: u8 = 0x02;
+
+fn keccak(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+#[derive(Clone)]
+pub struct TaggedKeccak256Algorithm;
+
+impl Hasher for TaggedKeccak256Algorithm {
+    type Hash = [u8; 32];
+    fn hash(data: &[u8]) -> [u8; 32] {
+        keccak(&[data])
+    }
+
+    // Promotes odd nodes like the default implementation, but prefixes the pair
+    fn concat_and_hash(left: &[u8; 32], right: Option<&[u8; 32]>) -> [u8; 32] {
+        match right {
+            Some(right) => hash_node(left, right),
+            None => *left,
+        }
+    }
+}
+
+// Hash of encoded leaf values (see `LeafEncoding`)
+pub fn hash_leaf(data: &[u8]) -> [u8; 32] {
+    keccak(&[&[LEAF_PREFIX], data])
+}
+
+pub fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    keccak(&[&[NODE_PREFIX], left, right])
+}
+
+// Bind the root of the tree to its number of leaves
+pub fn hash_root(num_leaves: u64, tree_root: &[u8; 32]) -> [u8; 32] {
+    keccak(&[&[ROOT_PREFIX], &num_leaves.to_be_bytes(), tree_root])
+}
+
+pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let tree = MerkleTree::<TaggedKeccak256Algorithm>::from_leaves(leaves);
+    let tree_root = tree.root().ok_or("failed to compute root").unwrap();
+    hash_root(leaves.len() as u64, &tree_root)
+}
+
+// Sibling paths of every leaf in the tree below the root (see `hash_root`)
+pub fn proofs(leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+    let tree = MerkleTree::<TaggedKeccak256Algorithm>::from_leaves(leaves);
+    (0..leaves.len())
+        .map(|index| tree.proof(&[index]).proof_hashes().to_vec())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leaves_and_nodes_are_separated() {
+        let (a, b) = ([1; 32], [2; 32]);
+        // A leaf over the 64 bytes of a pair does not hash to the pair's node
+        let mut pair = vec![];
+        pair.extend_from_slice(&a);
+        pair.extend_from_slice(&b);
+        assert_ne!(hash_leaf(&pair), hash_node(&a, &b));
+        assert_ne!(hash_node(&a, &b), keccak(&[&a, &b]));
+    }
+
+    #[test]
+    fn test_root_commits_to_num_leaves() {
+        let leaves: Vec<[u8; 32]> = (0..4u8).map(|i| hash_leaf(&[i; 20])).collect();
+        // The internal nodes of a tree, taken as leaves, do not rebuild its root
+        let nodes = [
+            hash_node(&leaves[0], &leaves[1]),
+            hash_node(&leaves[2], &leaves[3]),
+        ];
+        assert_eq!(
+            MerkleTree::<TaggedKeccak256Algorithm>::from_leaves(&nodes).root(),
+            MerkleTree::<TaggedKeccak256Algorithm>::from_leaves(&leaves).root()
+        );
+        assert_ne!(root(&nodes), root(&leaves));
+    }
+}
@@ -0,0 +1,267 @@
+// Statistical tests checking that `draw` selects winners with the probabilities implied by the
+// weights. Seeds are derived deterministically so that every run tests the same sample.
+//
+// Drawing k winners is weighted sampling without replacement: the i-th winner is the owner of
+// the i-th ticket in the permutation that does not belong to a previous winner, so each
+// remaining entry is picked with probability w / (W - weight of previous winners).
+use super::{draw, feistel::deshuffle, FEISTEL_ROUNDS};
+use crate::test_util::entries;
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+
+const NUM_SEEDS: u64 = 20_000;
+// Standard normal quantile for a significance level of 0.001
+const Z_CRITICAL: f64 = 3.090;
+// Two-sided z-test quantile for a significance level of 0.001 Bonferroni-corrected for the 16
+// inclusion probabilities tested
+const Z_CRITICAL_INCLUSION: f64 = 4.0;
+// Kolmogorov-Smirnov coefficient for a significance level of 0.001
+const KS_CRITICAL: f64 = 1.949;
+// Pearson's approximation is only valid for cells with enough expected observations
+const MIN_EXPECTED: f64 = 5.0;
+
+fn seed(i: u64) -> [u8; 32] {
+    Keccak256::digest(i.to_be_bytes()).into()
+}
+
+fn index_of(address: &[u8; 20]) -> usize {
+    address[0] as usize - 1
+}
+
+// Probability of every ordered sequence of k distinct winners under weighted sampling without
+// replacement
+fn sequence_probabilities(weights: &[u64], k: usize) -> HashMap<Vec<usize>, f64> {
+    fn extend(
+        weights: &[u64],
+        k: usize,
+        prefix: &mut Vec<usize>,
+        p: f64,
+        out: &mut HashMap<Vec<usize>, f64>,
+    ) {
+        if prefix.len() == k {
+            out.insert(prefix.clone(), p);
+            return;
+        }
+        let remaining: u64 = (0..weights.len())
+            .filter(|i| !prefix.contains(i))
+            .map(|i| weights[i])
+            .sum();
+        for i in 0..weights.len() {
+            if prefix.contains(&i) {
+                continue;
+            }
+            prefix.push(i);
+            extend(
+                weights,
+                k,
+                prefix,
+                p * weights[i] as f64 / remaining as f64,
+                out,
+            );
+            prefix.pop();
+        }
+    }
+
+    let mut out = HashMap::new();
+    extend(weights, k, &mut vec![], 1.0, &mut out);
+    out
+}
+
+// Probability of each entry being among the k winners
+fn inclusion_probabilities(weights: &[u64], k: usize) -> Vec<f64> {
+    let mut inclusion = vec![0.0; weights.len()];
+    for (sequence, p) in sequence_probabilities(weights, k) {
+        for i in sequence {
+            inclusion[i] += p;
+        }
+    }
+    inclusion
+}
+
+// Upper critical value of the chi-squared distribution (Wilson-Hilferty approximation)
+fn chi_squared_critical(df: usize) -> f64 {
+    let df = df as f64;
+    let c = 2.0 / (9.0 * df);
+    df * (1.0 - c + Z_CRITICAL * c.sqrt()).powi(3)
+}
+
+// Pearson's chi-squared goodness-of-fit test; cells with too few expected observations are
+// pooled together
+fn assert_chi_squared<K: Eq + std::hash::Hash>(
+    observed: &HashMap<K, u64>,
+    probabilities: &HashMap<K, f64>,
+    n: u64,
+) {
+    assert!(
+        observed.keys().all(|key| probabilities.contains_key(key)),
+        "observed an outcome with zero probability"
+    );
+
+    let mut statistic = 0.0;
+    let mut cells = 0;
+    let (mut pooled_observed, mut pooled_expected) = (0.0, 0.0);
+    for (key, p) in probabilities {
+        let observed = *observed.get(key).unwrap_or(&0) as f64;
+        let expected = p * n as f64;
+        if expected < MIN_EXPECTED {
+            pooled_observed += observed;
+            pooled_expected += expected;
+            continue;
+        }
+        statistic += (observed - expected).powi(2) / expected;
+        cells += 1;
+    }
+    if pooled_expected > 0.0 {
+        statistic += (pooled_observed - pooled_expected).powi(2) / pooled_expected;
+        cells += 1;
+    }
+
+    let critical = chi_squared_critical(cells - 1);
+    assert!(
+        statistic < critical,
+        "chi-squared statistic {statistic} >= {critical} ({} degrees of freedom)",
+        cells - 1
+    );
+}
+
+// Kolmogorov-Smirnov statistic of sorted samples in [0, 1) against the uniform distribution
+fn ks_uniform(samples: &[f64]) -> f64 {
+    let n = samples.len() as f64;
+    samples
+        .iter()
+        .enumerate()
+        .map(|(i, x)| ((i + 1) as f64 / n - x).max(x - i as f64 / n))
+        .fold(0.0, f64::max)
+}
+
+fn draw_sequences(weights: &[u64], k: u64) -> HashMap<Vec<usize>, u64> {
+    let entries = entries(weights);
+    let mut observed = HashMap::new();
+    for i in 0..NUM_SEEDS {
+        let winners = draw(k, &[], seed(i), &entries);
+        let sequence = winners.iter().map(index_of).collect::<Vec<_>>();
+        *observed.entry(sequence).or_insert(0) += 1;
+    }
+    observed
+}
+
+#[test]
+fn test_single_winner_chi_squared() {
+    for weights in [
+        vec![1, 2, 4, 8, 16, 32, 64, 128],
+        vec![3, 5, 7, 11, 13, 17, 19, 925],
+    ] {
+        let observed = draw_sequences(&weights, 1);
+        assert_chi_squared(&observed, &sequence_probabilities(&weights, 1), NUM_SEEDS);
+    }
+}
+
+#[test]
+fn test_single_winner_whale_chi_squared() {
+    // One entry holds 95% of the weight, 50 entries share the rest
+    let mut weights = vec![1; 50];
+    weights.push(950);
+    let observed = draw_sequences(&weights, 1);
+    assert_chi_squared(&observed, &sequence_probabilities(&weights, 1), NUM_SEEDS);
+}
+
+#[test]
+fn test_single_winner_ks() {
+    // Compare the empirical CDF of the winning entry with the CDF implied by the weights
+    let weights = vec![1, 2, 4, 8, 16, 32, 64, 128];
+    let total: u64 = weights.iter().sum();
+    let observed = draw_sequences(&weights, 1);
+
+    let mut d = 0.0f64;
+    let (mut empirical, mut theoretical) = (0.0, 0.0);
+    for (i, weight) in weights.iter().enumerate() {
+        empirical += *observed.get(&vec![i]).unwrap_or(&0) as f64 / NUM_SEEDS as f64;
+        theoretical += *weight as f64 / total as f64;
+        d = d.max((empirical - theoretical).abs());
+    }
+    let critical = KS_CRITICAL / (NUM_SEEDS as f64).sqrt();
+    assert!(d < critical, "KS statistic {d} >= {critical}");
+}
+
+#[test]
+fn test_ticket_uniformity_ks() {
+    // The winning ticket of every draw index must be uniform over the domain
+    let domain = 1_000_003;
+    for n in [0, 1, 7] {
+        let mut tickets = (0..NUM_SEEDS)
+            .map(|i| {
+                let trunc_seed = u64::from_be_bytes(seed(i)[24..32].try_into().unwrap());
+                deshuffle(n, trunc_seed, domain, FEISTEL_ROUNDS) as f64 / domain as f64
+            })
+            .collect::<Vec<_>>();
+        tickets.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let d = ks_uniform(&tickets);
+        let critical = KS_CRITICAL / (NUM_SEEDS as f64).sqrt();
+        assert!(d < critical, "KS statistic {d} >= {critical} for n = {n}");
+    }
+}
+
+#[test]
+fn test_ordered_winners_chi_squared() {
+    // Every ordered pair of winners must occur with its sampling-without-replacement probability
+    let weights = vec![1, 2, 4, 8, 16, 32];
+    let observed = draw_sequences(&weights, 2);
+    assert_chi_squared(&observed, &sequence_probabilities(&weights, 2), NUM_SEEDS);
+}
+
+#[test]
+fn test_winner_sets_chi_squared() {
+    let weights = vec![1, 3, 9, 27, 81, 243];
+    let k = 3;
+    let mut observed = HashMap::new();
+    for (mut sequence, count) in draw_sequences(&weights, k) {
+        sequence.sort();
+        *observed.entry(sequence).or_insert(0) += count;
+    }
+    let mut probabilities = HashMap::new();
+    for (mut sequence, p) in sequence_probabilities(&weights, k as usize) {
+        sequence.sort();
+        *probabilities.entry(sequence).or_insert(0.0) += p;
+    }
+    assert_chi_squared(&observed, &probabilities, NUM_SEEDS);
+}
+
+#[test]
+fn test_inclusion_probabilities() {
+    // Each entry's inclusion frequency must match its theoretical inclusion probability,
+    // which for skewed weights is far from proportional to the weight
+    let weights = vec![1, 2, 4, 8, 16, 32, 64, 128];
+    for k in [2, 4] {
+        let inclusion = inclusion_probabilities(&weights, k);
+        let mut counts = vec![0u64; weights.len()];
+        for (sequence, count) in draw_sequences(&weights, k as u64) {
+            for i in sequence {
+                counts[i] += count;
+            }
+        }
+
+        for (i, p) in inclusion.iter().enumerate() {
+            let expected = p * NUM_SEEDS as f64;
+            let sd = (NUM_SEEDS as f64 * p * (1.0 - p)).sqrt();
+            let z = (counts[i] as f64 - expected) / sd;
+            assert!(
+                z.abs() < Z_CRITICAL_INCLUSION,
+                "entry {i} included {} times, expected {expected:.1} (z = {z:.2}, k = {k})",
+                counts[i]
+            );
+        }
+    }
+}
+
+#[test]
+fn test_sequence_probabilities() {
+    let weights = vec![1, 2, 3, 4];
+    for k in 1..=4 {
+        let total: f64 = sequence_probabilities(&weights, k).values().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        let inclusion: f64 = inclusion_probabilities(&weights, k).iter().sum();
+        assert!((inclusion - k as f64).abs() < 1e-9);
+    }
+    // P(4 then 3) = 4/10 * 3/6
+    assert!((sequence_probabilities(&weights, 2)[&vec![3, 2]] - 0.2).abs() < 1e-9);
+}
@@ -1,8 +1,8 @@
 use sha3::{Digest, Keccak256};
 
 // Babylonian sqrt
-fn sqrt(s: u64) -> u64 {
-    #[cfg(not(test))]
+pub fn sqrt(s: u64) -> u64 {
+    #[cfg(target_os = "zkvm")]
     println!("cycle-tracker-start: sqrt");
 
     let mut z = 0;
@@ -17,14 +17,14 @@ fn sqrt(s: u64) -> u64 {
         z = 1;
     }
 
-    #[cfg(not(test))]
+    #[cfg(target_os = "zkvm")]
     println!("cycle-tracker-end: sqrt");
     z
 }
 
 // Take next perfect square unless n is already one
 fn next_perfect_square(n: u64) -> u64 {
-    #[cfg(not(test))]
+    #[cfg(target_os = "zkvm")]
     println!("cycle-tracker-start: next-perfect-square");
     let sqrt_n = sqrt(n);
     if sqrt_n.pow(2) == n {
@@ -32,13 +32,13 @@ fn next_perfect_square(n: u64) -> u64 {
     }
     let out = (sqrt_n + 1).pow(2);
 
-    #[cfg(not(test))]
+    #[cfg(target_os = "zkvm")]
     println!("cycle-tracker-end: next-perfect-square");
     out
 }
 
 fn f(x: u64, i: u64, seed: u64, modulus: u64) -> u64 {
-    #[cfg(not(test))]
+    #[cfg(target_os = "zkvm")]
     println!("cycle-tracker-start: round-func");
 
     let mut keccak = Keccak256::new();
@@ -50,7 +50,7 @@ fn f(x: u64, i: u64, seed: u64, modulus: u64) -> u64 {
     let trunc_output = output[24..32].try_into().unwrap();
     let out = u64::from_be_bytes(trunc_output);
 
-    #[cfg(not(test))]
+    #[cfg(target_os = "zkvm")]
     println!("cycle-tracker-end: round-func");
     out
 }
@@ -61,7 +61,7 @@ pub fn shuffle(_x: u64, seed: u64, domain: u64, rounds: u64) -> u64 {
     assert!(_x < domain, "x too large");
     assert!((rounds & 1) == 0, "rounds must be even");
 
-    #[cfg(not(test))]
+    #[cfg(target_os = "zkvm")]
     println!("cycle-tracker-start: shuffle");
     let mut x = _x;
     let h = sqrt(next_perfect_square(domain));
@@ -79,7 +79,7 @@ pub fn shuffle(_x: u64, seed: u64, domain: u64, rounds: u64) -> u64 {
         }
     }
 
-    #[cfg(not(test))]
+    #[cfg(target_os = "zkvm")]
     println!("cycle-tracker-end: shuffle");
     x
 }
@@ -90,7 +90,7 @@ pub fn deshuffle(_x_prime: u64, seed: u64, domain: u64, rounds: u64) -> u64 {
     assert!(_x_prime < domain, "x too large {_x_prime} >= {domain}");
     assert!((rounds & 1) == 0, "rounds must be even");
 
-    #[cfg(not(test))]
+    #[cfg(target_os = "zkvm")]
     println!("cycle-tracker-start: deshuffle");
     let mut x_prime = _x_prime;
     let h = sqrt(next_perfect_square(domain));
@@ -108,7 +108,7 @@ pub fn deshuffle(_x_prime: u64, seed: u64, domain: u64, rounds: u64) -> u64 {
         }
     }
 
-    #[cfg(not(test))]
+    #[cfg(target_os = "zkvm")]
     println!("cycle-tracker-end: deshuffle");
     x_prime
 }
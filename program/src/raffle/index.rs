@@ -0,0 +1,194 @@
+use super::Entry;
+use std::fmt;
+
+// Reason a list of entries does not form a valid partition of the tickets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidEntries {
+    TooFew,
+    FirstStartNonZero,
+    // Entry at the given position has a non-positive weight
+    EmptyRange(usize),
+    // Entry at the given position does not start where the previous one ends
+    NonAdjacent(usize),
+    // Entry at the given position does not have a greater address than the previous one
+    Unordered(usize),
+}
+
+impl fmt::Display for InvalidEntries {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidEntries::TooFew => write!(f, "<2 entries"),
+            InvalidEntries::FirstStartNonZero => write!(f, "first entry must start at 0"),
+            InvalidEntries::EmptyRange(i) => write!(f, "invalid entry (entry {i})"),
+            InvalidEntries::NonAdjacent(i) => write!(f, "non-adjacent entries (entry {i})"),
+            InvalidEntries::Unordered(i) => {
+                write!(f, "entries must be ordered (asc) by addresses (entry {i})")
+            }
+        }
+    }
+}
+
+// Check the entry at position i against the one before it, so that entries can be validated one
+// at a time as they are streamed (the number of entries is checked separately)
+pub fn check_entry(
+    i: usize,
+    last_entry: Option<&Entry>,
+    entry: &Entry,
+) -> Result<(), InvalidEntries> {
+    // Invariant: first entry must start at 0
+    if i == 0 && entry.start != 0 {
+        return Err(InvalidEntries::FirstStartNonZero);
+    }
+    // Invariant: weight must be positive
+    if entry.start >= entry.end {
+        return Err(InvalidEntries::EmptyRange(i));
+    }
+    if let Some(last_entry) = last_entry {
+        // Invariant: entries must be adjacent segments
+        if last_entry.end != entry.start {
+            return Err(InvalidEntries::NonAdjacent(i));
+        }
+        // Invariant: addresses are identities and must be distinct
+        if last_entry.address >= entry.address {
+            return Err(InvalidEntries::Unordered(i));
+        }
+    }
+    Ok(())
+}
+
+// Entries validated once so that tickets can be mapped to entries without further checks
+#[derive(Debug, Clone, Copy)]
+pub struct EntryIndex<'a> {
+    entries: &'a [Entry],
+}
+
+impl<'a> EntryIndex<'a> {
+    pub fn new(entries: &'a [Entry]) -> Result<Self, InvalidEntries> {
+        if entries.len() < 2 {
+            return Err(InvalidEntries::TooFew);
+        }
+        for (i, entry) in entries.iter().enumerate() {
+            check_entry(i, i.checked_sub(1).map(|j| &entries[j]), entry)?;
+        }
+        Ok(EntryIndex { entries })
+    }
+
+    pub fn entries(&self) -> &'a [Entry] {
+        self.entries
+    }
+
+    // Number of tickets, i.e. the end of the last entry
+    pub fn total_weight(&self) -> u64 {
+        self.entries[self.entries.len() - 1].end
+    }
+
+    // Entry whose [start, end) range contains the ticket, or None if ticket >= total weight
+    pub fn lookup(&self, ticket: u64) -> Option<&'a Entry> {
+        // Ranges are adjacent and start at 0, so the first entry ending after the ticket holds it
+        let i = self.entries.partition_point(|entry| entry.end <= ticket);
+        self.entries.get(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::entries;
+    use rand::Rng;
+
+    fn linear_lookup(entries: &[Entry], ticket: u64) -> Option<&Entry> {
+        entries
+            .iter()
+            .find(|entry| entry.start <= ticket && ticket < entry.end)
+    }
+
+    #[test]
+    fn test_lookup_exhaustive() {
+        for weights in [
+            vec![1, 1],
+            vec![1, 2, 3],
+            vec![10, 1, 1, 10],
+            vec![7; 17],
+            vec![1, 100, 1, 100, 1],
+        ] {
+            let entries = entries(&weights);
+            let index = EntryIndex::new(&entries).unwrap();
+            let total = index.total_weight();
+            for ticket in 0..total {
+                assert_eq!(
+                    index.lookup(ticket).map(|entry| entry.address),
+                    linear_lookup(&entries, ticket).map(|entry| entry.address),
+                    "wrong entry for ticket {ticket}"
+                );
+            }
+            assert!(index.lookup(total).is_none());
+            assert!(index.lookup(u64::MAX).is_none());
+        }
+    }
+
+    #[test]
+    fn test_lookup_boundaries() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let weights = (0..rng.gen_range(2..200))
+                .map(|_| rng.gen_range(1..1_000_000))
+                .collect::<Vec<u64>>();
+            let entries = entries(&weights);
+            let index = EntryIndex::new(&entries).unwrap();
+
+            // First and last ticket of every entry, including the first and last entries
+            for entry in &entries {
+                assert_eq!(index.lookup(entry.start).unwrap().address, entry.address);
+                assert_eq!(index.lookup(entry.end - 1).unwrap().address, entry.address);
+            }
+            assert_eq!(index.lookup(0).unwrap().address, entries[0].address);
+            let last = entries.last().unwrap();
+            assert_eq!(index.lookup(last.end - 1).unwrap().address, last.address);
+            assert!(index.lookup(last.end).is_none());
+
+            // Random tickets
+            for _ in 0..100 {
+                let ticket = rng.gen_range(0..index.total_weight());
+                assert_eq!(
+                    index.lookup(ticket).unwrap().address,
+                    linear_lookup(&entries, ticket).unwrap().address
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_entries() {
+        assert_eq!(
+            EntryIndex::new(&entries(&[10])).unwrap_err(),
+            InvalidEntries::TooFew
+        );
+
+        let mut shifted = entries(&[10, 10]);
+        shifted[0].start = 1;
+        assert_eq!(
+            EntryIndex::new(&shifted).unwrap_err(),
+            InvalidEntries::FirstStartNonZero
+        );
+
+        assert_eq!(
+            EntryIndex::new(&entries(&[10, 0, 10])).unwrap_err(),
+            InvalidEntries::EmptyRange(1)
+        );
+
+        let mut gap = entries(&[10, 10, 10]);
+        gap[2].start += 1;
+        gap[2].end += 1;
+        assert_eq!(
+            EntryIndex::new(&gap).unwrap_err(),
+            InvalidEntries::NonAdjacent(2)
+        );
+
+        let mut unordered = entries(&[10, 10, 10]);
+        unordered[1].address = unordered[0].address;
+        assert_eq!(
+            EntryIndex::new(&unordered).unwrap_err(),
+            InvalidEntries::Unordered(1)
+        );
+    }
+}
@@ -1,75 +1,322 @@
+#[cfg(test)]
+mod fairness;
 pub mod feistel;
+pub mod index;
+pub mod sublinear;
+pub mod weight;
 
+use alloy_primitives::Address;
+use alloy_sol_types::{sol, SolType};
 use feistel::deshuffle;
+use index::EntryIndex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use sha3::{Digest, Keccak256};
+use std::collections::{HashMap, HashSet};
+
+// Rounds of the Feistel network permuting the tickets (see `feistel`)
+pub const FEISTEL_ROUNDS: u64 = 4;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Entry {
     pub address: [u8; 20],
     pub start: u64,
     pub end: u64,
+    pub group: Option<u32>,
 }
 
-fn compute_winner(n: u64, entries: &[Entry], seed: [u8; 32]) -> [u8; 20] {
-    let last_entry = entries.last().unwrap();
-    let domain = last_entry.end;
-    let trunc_seed = u64::from_be_bytes(seed[24..32].try_into().unwrap());
-    let winning_index = deshuffle(n, trunc_seed, domain, 4);
-
-    let mut l = 0u64;
-    let mut r = entries.len() as u64;
-    while l <= r {
-        let m = (l + r) / 2;
-        let entry = &entries[m as usize];
-        if entry.start <= winning_index && winning_index < entry.end {
-            return entry.address;
-        } else if entry.start > winning_index {
-            r = m - 1;
-        } else {
-            l = m + 1;
-        }
+// Number of winners guaranteed to entries of a group
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Quota {
+    pub group: u32,
+    pub num_winners: u64,
+}
+
+type QuotasArray = sol! {
+    // (group, num_winners)[]
+    tuple(uint32,uint64)[]
+};
+
+// Quotas committed in the public values: keccak256(abi.encode(quotas)), the quotas being a
+// (uint32 group, uint64 numWinners)[] in input order, or zero without quotas
+pub fn quotas_hash(quotas: &[Quota]) -> [u8; 32] {
+    if quotas.is_empty() {
+        return [0; 32];
     }
-    panic!("list exhausted without finding entry");
+    let quotas: Vec<(u32, u64)> = quotas
+        .iter()
+        .map(|quota| (quota.group, quota.num_winners))
+        .collect();
+    Keccak256::digest(QuotasArray::abi_encode(&quotas)).into()
+}
+
+// Outcome of drawing a single index of the ticket permutation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    // The entry wins
+    Winner,
+    // The entry has already won, the index is skipped
+    AlreadyWon,
+    // Neither the entry's group quota nor the open winners have room left, the index is skipped
+    QuotaFilled,
+}
+
+// A single index of the ticket permutation, its ticket and the entry the ticket falls in
+#[derive(Debug, Clone, Copy)]
+pub struct DrawStep<'a> {
+    pub index: u64,
+    pub ticket: u64,
+    pub entry: &'a Entry,
+    pub outcome: Outcome,
+}
+
+// How the winner at some position of the draw was selected
+#[derive(Debug, Clone)]
+pub struct DrawExplanation<'a> {
+    // Position of the winner in the draw, 0 being the first winner
+    pub winner_index: u64,
+    pub winner: DrawStep<'a>,
+    // Every earlier index of the permutation that did not produce a winner
+    pub skipped: Vec<DrawStep<'a>>,
 }
 
-pub fn draw(num_winners: u64, seed: [u8; 32], entries: &[Entry]) -> Vec<[u8; 20]> {
-    assert!(num_winners > 0, "num_winners == 0");
-    assert!(
-        num_winners <= entries.len() as u64,
-        "num_winners > |entries|"
-    );
+// State of a draw walking the seed-derived permutation of tickets
+struct Drawing<'a> {
+    index: EntryIndex<'a>,
+    trunc_seed: u64,
+    // Winners left to draw per group quota
+    remaining: HashMap<u32, u64>,
+    // Winners left to draw that are open to any entry
+    open: u64,
+    drawn: HashSet<[u8; 20]>,
+    // Next index of the ticket permutation
+    position: u64,
+}
+
+impl<'a> Drawing<'a> {
+    fn new(num_winners: u64, quotas: &[Quota], seed: [u8; 32], entries: &'a [Entry]) -> Self {
+        let index = EntryIndex::new(entries).unwrap_or_else(|err| panic!("{err}"));
+        assert!(num_winners > 0, "num_winners == 0");
+        assert!(
+            num_winners <= entries.len() as u64,
+            "num_winners > |entries|"
+        );
+
+        let mut remaining = HashMap::new();
+        let mut open = num_winners;
+        for quota in quotas {
+            assert!(
+                remaining.insert(quota.group, quota.num_winners).is_none(),
+                "duplicate quota"
+            );
+            let group_size = entries
+                .iter()
+                .filter(|entry| entry.group == Some(quota.group))
+                .count();
+            assert!(quota.num_winners <= group_size as u64, "quota > |group|");
+            open = open
+                .checked_sub(quota.num_winners)
+                .expect("sum of quotas > num_winners");
+        }
+
+        Drawing {
+            index,
+            trunc_seed: u64::from_be_bytes(seed[24..32].try_into().unwrap()),
+            remaining,
+            open,
+            drawn: HashSet::new(),
+            position: 0,
+        }
+    }
+
+    fn step(&mut self) -> DrawStep<'a> {
+        let index = self.position;
+        self.position += 1;
+        let ticket = deshuffle(
+            index,
+            self.trunc_seed,
+            self.index.total_weight(),
+            FEISTEL_ROUNDS,
+        );
+        // deshuffle permutes [0, total weight), so every ticket belongs to an entry
+        let entry = self.index.lookup(ticket).unwrap();
+
+        let outcome = if self.drawn.contains(&entry.address) {
+            Outcome::AlreadyWon
+        } else {
+            match entry
+                .group
+                .and_then(|group| self.remaining.get_mut(&group))
+                .filter(|left| **left > 0)
+            {
+                Some(left) => {
+                    *left -= 1;
+                    Outcome::Winner
+                }
+                None if self.open > 0 => {
+                    self.open -= 1;
+                    Outcome::Winner
+                }
+                None => Outcome::QuotaFilled,
+            }
+        };
+        if outcome == Outcome::Winner {
+            self.drawn.insert(entry.address);
+        }
+
+        DrawStep {
+            index,
+            ticket,
+            entry,
+            outcome,
+        }
+    }
 
-    let mut winners = HashSet::new();
-    let mut i = 0u64;
-    for _ in 0..num_winners {
+    fn next_winner(&mut self) -> DrawStep<'a> {
         loop {
-            let winner = compute_winner(i, entries, seed);
-            i += 1;
-            if !winners.contains(&winner) {
-                winners.insert(winner);
-                break;
+            let step = self.step();
+            if step.outcome == Outcome::Winner {
+                return step;
             }
         }
     }
-    winners.into_iter().collect()
+
+    // Walk the draw until the winner matching `is_target` (given its position), recording the
+    // skipped indices on the way
+    fn explain(
+        mut self,
+        num_winners: u64,
+        is_target: impl Fn(u64, &DrawStep) -> bool,
+    ) -> Option<DrawExplanation<'a>> {
+        let mut skipped = vec![];
+        let mut winner_index = 0;
+        while winner_index < num_winners {
+            let step = self.step();
+            if step.outcome != Outcome::Winner {
+                skipped.push(step);
+                continue;
+            }
+            if is_target(winner_index, &step) {
+                return Some(DrawExplanation {
+                    winner_index,
+                    winner: step,
+                    skipped,
+                });
+            }
+            winner_index += 1;
+        }
+        None
+    }
+}
+
+// Draw distinct winners in the order of the seed-derived permutation of tickets
+// Each quota reserves winners for entries of its group; the remaining num_winners - sum(quotas)
+// winners are open to any entry. An entry whose group quota is filled can only take an open slot.
+pub fn draw(
+    num_winners: u64,
+    quotas: &[Quota],
+    seed: [u8; 32],
+    entries: &[Entry],
+) -> Vec<[u8; 20]> {
+    let mut drawing = Drawing::new(num_winners, quotas, seed, entries);
+    (0..num_winners)
+        .map(|_| drawing.next_winner().entry.address)
+        .collect()
+}
+
+// Every index of the permutation walked to draw the winners, in order
+pub fn draw_steps<'a>(
+    num_winners: u64,
+    quotas: &[Quota],
+    seed: [u8; 32],
+    entries: &'a [Entry],
+) -> Vec<DrawStep<'a>> {
+    let mut drawing = Drawing::new(num_winners, quotas, seed, entries);
+    let mut steps = vec![];
+    let mut winners = 0;
+    while winners < num_winners {
+        let step = drawing.step();
+        if step.outcome == Outcome::Winner {
+            winners += 1;
+        }
+        steps.push(step);
+    }
+    steps
+}
+
+// Recompute only the prefix of the draw up to the winner at position `winner_index`
+pub fn winner_at<'a>(
+    winner_index: u64,
+    num_winners: u64,
+    quotas: &[Quota],
+    seed: [u8; 32],
+    entries: &'a [Entry],
+) -> DrawExplanation<'a> {
+    assert!(winner_index < num_winners, "winner_index >= num_winners");
+    Drawing::new(num_winners, quotas, seed, entries)
+        .explain(num_winners, |i, _| i == winner_index)
+        .unwrap()
+}
+
+// Explain how `address` won, or return None if it is not among the winners
+pub fn explain_draw<'a>(
+    address: &[u8; 20],
+    num_winners: u64,
+    quotas: &[Quota],
+    seed: [u8; 32],
+    entries: &'a [Entry],
+) -> Option<DrawExplanation<'a>> {
+    Drawing::new(num_winners, quotas, seed, entries)
+        .explain(num_winners, |_, step| step.entry.address == *address)
+}
+
+type SeedBindingTuple = sol! {
+    // seed, chain_id, raffle_contract, raffle_id
+    tuple(bytes32,uint64,address,uint64)
+};
+
+// Seed the draw is run with: keccak256(abi.encode(seed, chainId, raffleContract, raffleId)), so
+// that the same seed draws differently, and a proof cannot be replayed, in another raffle
+pub fn bind_seed(
+    seed: [u8; 32],
+    chain_id: u64,
+    raffle_contract: [u8; 20],
+    raffle_id: u64,
+) -> [u8; 32] {
+    let encoded =
+        SeedBindingTuple::abi_encode(&(seed, chain_id, Address::from(raffle_contract), raffle_id));
+    Keccak256::digest(encoded).into()
+}
+
+// Rank every entry in the order of the seed-derived permutation of tickets, i.e. draw every entry
+// as a winner. The first k ranked addresses are exactly the winners of a k-winner draw.
+// Note that this walks the permutation until the last entry is hit, which is O(total weight) in
+// the worst case.
+pub fn rank(seed: [u8; 32], entries: &[Entry]) -> Vec<[u8; 20]> {
+    draw(entries.len() as u64, &[], seed, entries)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{entries, grouped_entries};
     use rand::{self, Rng};
     use std::collections::HashSet;
 
     #[test]
     fn test_draw() {
         let num_entries = 1000;
+        // Entries must be ordered by address
+        let mut addresses: Vec<[u8; 20]> = (0..num_entries)
+            .map(|_| rand::thread_rng().r#gen())
+            .collect();
+        addresses.sort();
         let mut entries = vec![];
-        for i in 0..num_entries {
+        for (i, address) in (0..num_entries).zip(addresses) {
             entries.push(Entry {
-                address: rand::thread_rng().r#gen(),
+                address,
                 start: i * 10,
                 end: i * 10 + 10,
+                group: None,
             });
         }
         assert_eq!(
@@ -79,7 +326,7 @@ mod tests {
         );
         let num_winners = num_entries;
         let seed = rand::thread_rng().r#gen();
-        let winners = draw(num_winners, seed, &entries);
+        let winners = draw(num_winners, &[], seed, &entries);
         assert_eq!(
             winners.len(),
             num_winners as usize,
@@ -97,4 +344,235 @@ mod tests {
             "winners are not distinct"
         );
     }
+
+    #[test]
+    fn test_rank() {
+        let entries = entries(&[10; 100]);
+        let seed = rand::thread_rng().r#gen();
+        let ranking = rank(seed, &entries);
+        assert_eq!(
+            ranking.iter().collect::<HashSet<_>>(),
+            entries.iter().map(|entry| &entry.address).collect(),
+            "ranking is not a permutation of the entries"
+        );
+        assert_eq!(ranking[..10], draw(10, &[], seed, &entries));
+    }
+
+    #[test]
+    fn test_winner_at() {
+        let mut groups = vec![Some(1); 10];
+        groups.extend(vec![None; 40]);
+        let entries = grouped_entries(&[10; 50], &groups);
+        let quotas = vec![Quota {
+            group: 1,
+            num_winners: 5,
+        }];
+        let seed = rand::thread_rng().r#gen();
+        let winners = draw(10, &quotas, seed, &entries);
+
+        let mut last_index = None;
+        for (i, winner) in winners.iter().enumerate() {
+            let explanation = winner_at(i as u64, 10, &quotas, seed, &entries);
+            assert_eq!(explanation.winner_index, i as u64);
+            assert_eq!(explanation.winner.entry.address, *winner);
+            assert_eq!(explanation.winner.outcome, Outcome::Winner);
+            let step = explanation.winner;
+            assert!(step.entry.start <= step.ticket && step.ticket < step.entry.end);
+            assert!(last_index < Some(step.index), "winners out of order");
+            last_index = Some(step.index);
+
+            // Every earlier index is either skipped or produced an earlier winner
+            assert_eq!(
+                explanation.skipped.len() as u64 + i as u64,
+                step.index,
+                "skipped indices do not account for the permutation prefix"
+            );
+            for skipped in &explanation.skipped {
+                match skipped.outcome {
+                    Outcome::AlreadyWon => {
+                        assert!(winners[..i].contains(&skipped.entry.address))
+                    }
+                    // Group 1 entries still have quota left until the last winner is drawn, so
+                    // only ungrouped entries can run out of (open) winners
+                    Outcome::QuotaFilled => assert!(skipped.entry.group.is_none()),
+                    Outcome::Winner => panic!("winner recorded as skipped"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_explain_draw() {
+        let entries = entries(&[10; 50]);
+        let seed = rand::thread_rng().r#gen();
+        let winners = draw(10, &[], seed, &entries);
+        for (i, winner) in winners.iter().enumerate() {
+            let explanation = explain_draw(winner, 10, &[], seed, &entries).unwrap();
+            assert_eq!(explanation.winner_index, i as u64);
+        }
+        let loser = entries
+            .iter()
+            .find(|entry| !winners.contains(&entry.address))
+            .unwrap();
+        assert!(explain_draw(&loser.address, 10, &[], seed, &entries).is_none());
+    }
+
+    fn count_group(winners: &[[u8; 20]], entries: &[Entry], group: Option<u32>) -> usize {
+        winners
+            .iter()
+            .filter(|winner| {
+                entries
+                    .iter()
+                    .any(|entry| entry.address == **winner && entry.group == group)
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_draw_quotas() {
+        let mut groups = vec![Some(1); 20];
+        groups.extend(vec![Some(2); 5]);
+        groups.extend(vec![None; 25]);
+        let entries = grouped_entries(&[10; 50], &groups);
+        let quotas = vec![
+            Quota {
+                group: 1,
+                num_winners: 3,
+            },
+            Quota {
+                group: 2,
+                num_winners: 5,
+            },
+        ];
+        for _ in 0..20 {
+            let seed = rand::thread_rng().r#gen();
+            let winners = draw(10, &quotas, seed, &entries);
+            assert_eq!(winners.len(), 10, "wrong number of winners drawn");
+            assert_eq!(
+                winners.iter().collect::<HashSet<_>>().len(),
+                10,
+                "winners are not distinct"
+            );
+            assert!(count_group(&winners, &entries, Some(1)) >= 3, "quota unmet");
+            assert_eq!(count_group(&winners, &entries, Some(2)), 5, "quota unmet");
+        }
+    }
+
+    #[test]
+    fn test_draw_quotas_exact() {
+        let mut groups = vec![Some(1); 10];
+        groups.extend(vec![Some(2); 10]);
+        groups.extend(vec![None; 10]);
+        let entries = grouped_entries(&[10; 30], &groups);
+        let quotas = vec![
+            Quota {
+                group: 1,
+                num_winners: 4,
+            },
+            Quota {
+                group: 2,
+                num_winners: 2,
+            },
+        ];
+        let seed = rand::thread_rng().r#gen();
+        let winners = draw(6, &quotas, seed, &entries);
+        assert_eq!(count_group(&winners, &entries, Some(1)), 4);
+        assert_eq!(count_group(&winners, &entries, Some(2)), 2);
+        assert_eq!(count_group(&winners, &entries, None), 0);
+    }
+
+    #[test]
+    fn test_draw_deterministic() {
+        let entries = entries(&[10; 50]);
+        let seed = rand::thread_rng().r#gen();
+        assert_eq!(draw(10, &[], seed, &entries), draw(10, &[], seed, &entries));
+    }
+
+    #[test]
+    #[should_panic(expected = "non-adjacent entries")]
+    fn test_draw_asserts_valid_entries() {
+        let mut entries = entries(&[10; 3]);
+        entries[2].start += 1;
+        draw(1, &[], [0; 32], &entries);
+    }
+
+    #[test]
+    #[should_panic(expected = "quota > |group|")]
+    fn test_draw_asserts_quota_within_group() {
+        let entries = grouped_entries(&[10; 3], &[Some(1), Some(1), None]);
+        let quotas = vec![Quota {
+            group: 1,
+            num_winners: 3,
+        }];
+        draw(3, &quotas, [0; 32], &entries);
+    }
+
+    #[test]
+    #[should_panic(expected = "sum of quotas > num_winners")]
+    fn test_draw_asserts_quotas_within_num_winners() {
+        let entries = grouped_entries(&[10; 3], &[Some(1), Some(1), Some(2)]);
+        let quotas = vec![
+            Quota {
+                group: 1,
+                num_winners: 2,
+            },
+            Quota {
+                group: 2,
+                num_winners: 1,
+            },
+        ];
+        draw(2, &quotas, [0; 32], &entries);
+    }
+
+    #[test]
+    fn test_quotas_hash() {
+        assert_eq!(quotas_hash(&[]), [0; 32]);
+
+        let quotas = [
+            Quota {
+                group: 1,
+                num_winners: 2,
+            },
+            Quota {
+                group: 3,
+                num_winners: 4,
+            },
+        ];
+        // abi.encode: offset of the array, its length, then one word per field
+        let mut encoded = vec![];
+        for word in [0x20, 2, 1, 2, 3, 4] {
+            encoded.extend([[0; 31].as_slice(), &[word]].concat());
+        }
+        let hash = quotas_hash(&quotas);
+        assert_eq!(hash, <[u8; 32]>::from(Keccak256::digest(&encoded)));
+        // Quotas are hashed in input order
+        assert_ne!(hash, quotas_hash(&[quotas[1].clone(), quotas[0].clone()]));
+    }
+
+    #[test]
+    fn test_bind_seed() {
+        let seed = [7; 32];
+        let bound = bind_seed(seed, 1, [1; 20], 1);
+        let mut encoded = seed.to_vec();
+        encoded.extend([[0; 31].as_slice(), &[1]].concat());
+        encoded.extend([[0; 12].as_slice(), &[1; 20]].concat());
+        encoded.extend([[0; 31].as_slice(), &[1]].concat());
+        assert_eq!(bound, <[u8; 32]>::from(Keccak256::digest(&encoded)));
+        // Every binding field changes the seed
+        assert_ne!(bound, bind_seed(seed, 2, [1; 20], 1));
+        assert_ne!(bound, bind_seed(seed, 1, [2; 20], 1));
+        assert_ne!(bound, bind_seed(seed, 1, [1; 20], 2));
+        assert_ne!(bound, bind_seed([8; 32], 1, [1; 20], 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate quota")]
+    fn test_draw_asserts_distinct_quotas() {
+        let entries = grouped_entries(&[10; 3], &[Some(1), Some(1), None]);
+        let quota = Quota {
+            group: 1,
+            num_winners: 1,
+        };
+        draw(2, &[quota.clone(), quota], [0; 32], &entries);
+    }
 }
@@ -0,0 +1,270 @@
+// Draw that only reads the entries hit by its tickets, each proven against the commitment root, so
+// that its cost is O(k log n) instead of hashing all n entries
+// Each entry hit is proven once, at its first hit: later hits on the same entry (a heavy entry can
+// be hit many times before the last winner is drawn) only give its index.
+// The commitment root binds validated, adjacent [start, end) ranges (see `get_commitment_root`), so
+// the entry holding a ticket is the one whose proven range contains it. The total weight and number
+// of entries are not bound by the root: they are committed in the public values, to be checked
+// against the ones the root was registered with (see `merkle::register_entries`).
+// Quotas are not supported, as checking them needs the size of every group.
+use super::{deshuffle, Entry, FEISTEL_ROUNDS};
+use crate::merkle::{
+    leaf::entry_leaf,
+    proof::{verify_proof, LeafProof},
+    TreeKind,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// Entry hit by a ticket of the draw
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum DrawHit {
+    // First hit on an entry, with the proof of its leaf in the commitment tree, where `index` is
+    // the position of the entry
+    Proven {
+        entry: Entry,
+        index: u64,
+        proof: Vec<[u8; 32]>,
+    },
+    // Later hit on the entry proven at this position of the commitment tree
+    Repeat(u64),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SublinearDraw {
+    pub commitment_root: [u8; 32],
+    pub total_weight: u64,
+    pub entry_count: u64,
+    // Entry hit at each index of the ticket permutation, up to the last winner, each entry being
+    // proven at its first hit
+    pub hits: Vec<DrawHit>,
+}
+
+// Draw distinct winners in the order of the seed-derived permutation of tickets, like `draw`
+// without quotas
+pub fn draw_sublinear(
+    num_winners: u64,
+    seed: [u8; 32],
+    draw: &SublinearDraw,
+    kind: TreeKind,
+) -> Vec<[u8; 20]> {
+    assert!(num_winners > 0, "num_winners == 0");
+    assert!(num_winners <= draw.entry_count, "num_winners > |entries|");
+    // Invariant: every entry has a positive weight
+    assert!(
+        draw.total_weight >= draw.entry_count,
+        "total weight < |entries|"
+    );
+
+    let trunc_seed = u64::from_be_bytes(seed[24..32].try_into().unwrap());
+    let mut hits = draw.hits.iter();
+    // Entries proven so far, by position in the commitment tree
+    let mut proven = HashMap::new();
+    let mut drawn = HashSet::new();
+    let mut winners = vec![];
+    let mut position = 0;
+    while (winners.len() as u64) < num_winners {
+        let ticket = deshuffle(position, trunc_seed, draw.total_weight, FEISTEL_ROUNDS);
+        let hit = hits
+            .next()
+            .unwrap_or_else(|| panic!("missing hit entry (index {position})"));
+        let entry = match hit {
+            DrawHit::Proven {
+                entry,
+                index,
+                proof,
+            } => {
+                assert!(
+                    !proven.contains_key(index),
+                    "hit entry proven twice (index {position})"
+                );
+                let proof = LeafProof {
+                    address: entry.address,
+                    index: *index,
+                    leaf: kind.hash_leaf(&entry_leaf(entry)),
+                    proof: proof.clone(),
+                };
+                assert!(
+                    verify_proof(&draw.commitment_root, &proof, draw.entry_count, kind),
+                    "invalid proof of the hit entry (index {position})"
+                );
+                proven.insert(*index, entry);
+                entry
+            }
+            DrawHit::Repeat(index) => proven
+                .get(index)
+                .unwrap_or_else(|| panic!("unproven hit entry (index {position})")),
+        };
+        assert!(
+            entry.start <= ticket && ticket < entry.end,
+            "ticket {ticket} is not in the hit entry (index {position})"
+        );
+
+        if drawn.insert(entry.address) {
+            winners.push(entry.address);
+        }
+        position += 1;
+    }
+    assert!(hits.next().is_none(), "unused hit entries");
+    winners
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        merkle::{get_commitment_root, leaf::LeafEncoding, proof::get_sublinear_draw},
+        raffle::draw,
+        test_util::entries,
+    };
+
+    #[test]
+    fn test_sublinear_draw_matches_draw() {
+        // A heavy entry is hit several times before the last winner is drawn
+        let weights: Vec<u64> = (1..=40).map(|i| if i == 7 { 500 } else { i }).collect();
+        let entries = entries(&weights);
+        for kind in [
+            TreeKind::Positional(LeafEncoding::Packed),
+            TreeKind::OpenZeppelin,
+            TreeKind::Tagged(LeafEncoding::Abi),
+            TreeKind::Incremental(LeafEncoding::Packed),
+        ] {
+            for num_winners in [1, 3, 10, 40] {
+                for seed in [[7; 32], [42; 32]] {
+                    let sublinear = get_sublinear_draw(num_winners, seed, &entries, kind);
+                    assert_eq!(
+                        sublinear.commitment_root,
+                        get_commitment_root(&entries, kind)
+                    );
+                    assert_eq!(sublinear.entry_count, 40);
+                    let winners = draw(num_winners, &[], seed, &entries);
+                    assert_eq!(draw_sublinear(num_winners, seed, &sublinear, kind), winners);
+                    // Every winner is proven once, however many times it is hit
+                    let proven = sublinear
+                        .hits
+                        .iter()
+                        .filter(|hit| matches!(hit, DrawHit::Proven { .. }))
+                        .count();
+                    assert_eq!(proven, winners.len());
+                }
+            }
+        }
+    }
+
+    fn sublinear() -> SublinearDraw {
+        get_sublinear_draw(
+            5,
+            [7; 32],
+            &entries(&[3; 20]),
+            TreeKind::Positional(LeafEncoding::Packed),
+        )
+    }
+
+    // Position of a proven hit other than the first one
+    fn other_proven_hit(sublinear: &SublinearDraw) -> usize {
+        (1..sublinear.hits.len())
+            .find(|&i| matches!(sublinear.hits[i], DrawHit::Proven { .. }))
+            .unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "is not in the hit entry")]
+    fn test_sublinear_draw_rejects_wrong_entry() {
+        let mut sublinear = sublinear();
+        let other = sublinear.hits[other_proven_hit(&sublinear)].clone();
+        sublinear.hits[0] = other;
+        draw_sublinear(
+            5,
+            [7; 32],
+            &sublinear,
+            TreeKind::Positional(LeafEncoding::Packed),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid proof of the hit entry")]
+    fn test_sublinear_draw_rejects_forged_range() {
+        // An entry claiming a wider range than the one committed to
+        let mut sublinear = sublinear();
+        let DrawHit::Proven { entry, .. } = &mut sublinear.hits[0] else {
+            unreachable!("the first hit is always proven");
+        };
+        entry.start = 0;
+        entry.end = 60;
+        draw_sublinear(
+            5,
+            [7; 32],
+            &sublinear,
+            TreeKind::Positional(LeafEncoding::Packed),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unproven hit entry")]
+    fn test_sublinear_draw_rejects_unproven_repeats() {
+        let mut sublinear = sublinear();
+        let i = other_proven_hit(&sublinear);
+        let DrawHit::Proven { index, .. } = sublinear.hits[i] else {
+            unreachable!();
+        };
+        sublinear.hits[i] = DrawHit::Repeat(index);
+        draw_sublinear(
+            5,
+            [7; 32],
+            &sublinear,
+            TreeKind::Positional(LeafEncoding::Packed),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "hit entry proven twice")]
+    fn test_sublinear_draw_rejects_proofs_of_repeats() {
+        // A heavy entry is hit again before the last winner is drawn
+        let weights: Vec<u64> = (1..=40).map(|i| if i == 7 { 500 } else { i }).collect();
+        let kind = TreeKind::Positional(LeafEncoding::Packed);
+        let mut sublinear = get_sublinear_draw(10, [7; 32], &entries(&weights), kind);
+        let i = sublinear
+            .hits
+            .iter()
+            .position(|hit| matches!(hit, DrawHit::Repeat(_)))
+            .unwrap();
+        let DrawHit::Repeat(index) = sublinear.hits[i] else {
+            unreachable!();
+        };
+        let first = sublinear
+            .hits
+            .iter()
+            .position(
+                |hit| matches!(hit, DrawHit::Proven { index: proven, .. } if *proven == index),
+            )
+            .unwrap();
+        sublinear.hits[i] = sublinear.hits[first].clone();
+        draw_sublinear(10, [7; 32], &sublinear, kind);
+    }
+
+    #[test]
+    #[should_panic(expected = "missing hit entry")]
+    fn test_sublinear_draw_rejects_missing_hits() {
+        let mut sublinear = sublinear();
+        sublinear.hits.pop();
+        draw_sublinear(
+            5,
+            [7; 32],
+            &sublinear,
+            TreeKind::Positional(LeafEncoding::Packed),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unused hit entries")]
+    fn test_sublinear_draw_rejects_extra_hits() {
+        let mut sublinear = sublinear();
+        sublinear.hits.push(sublinear.hits[0].clone());
+        draw_sublinear(
+            5,
+            [7; 32],
+            &sublinear,
+            TreeKind::Positional(LeafEncoding::Packed),
+        );
+    }
+}
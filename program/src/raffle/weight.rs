@@ -0,0 +1,162 @@
+use super::{feistel::sqrt, Entry};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Balance {
+    pub address: [u8; 20],
+    pub balance: u64,
+    pub group: Option<u32>,
+}
+
+// Transformation applied to raw balances to obtain raffle weights
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightPolicy {
+    // weight = balance
+    Linear,
+    // weight = floor(sqrt(balance))
+    Sqrt,
+    // weight = min(balance, cap)
+    Capped(u64),
+    // weight = floor(log2(balance)) + 1
+    Log2,
+}
+
+impl WeightPolicy {
+    // Identifier committed in the public values
+    pub fn id(&self) -> u8 {
+        match self {
+            WeightPolicy::Linear => 0,
+            WeightPolicy::Sqrt => 1,
+            WeightPolicy::Capped(_) => 2,
+            WeightPolicy::Log2 => 3,
+        }
+    }
+
+    // Policy parameter committed in the public values (the cap, or 0 if there is none)
+    pub fn param(&self) -> u64 {
+        match self {
+            WeightPolicy::Capped(cap) => *cap,
+            _ => 0,
+        }
+    }
+
+    pub fn weight(&self, balance: u64) -> u64 {
+        match self {
+            WeightPolicy::Linear => balance,
+            WeightPolicy::Sqrt => sqrt(balance),
+            WeightPolicy::Capped(cap) => balance.min(*cap),
+            WeightPolicy::Log2 => (u64::BITS - balance.leading_zeros()) as u64,
+        }
+    }
+}
+
+// Derive adjacent [start, end) ranges from raw balances, in the same order as the balances
+pub fn derive_entries(balances: &[Balance], policy: WeightPolicy) -> Vec<Entry> {
+    if let WeightPolicy::Capped(cap) = policy {
+        assert!(cap > 0, "cap must be positive");
+    }
+
+    let mut entries = Vec::with_capacity(balances.len());
+    let mut start = 0u64;
+    for balance in balances {
+        // Invariant: every balance maps to a positive weight under all policies
+        assert!(balance.balance > 0, "zero balance");
+        let end = start
+            .checked_add(policy.weight(balance.balance))
+            .expect("total weight overflow");
+        entries.push(Entry {
+            address: balance.address,
+            start,
+            end,
+            group: balance.group,
+        });
+        start = end;
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balances(values: &[u64]) -> Vec<Balance> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &balance)| Balance {
+                address: [i as u8 + 1; 20],
+                balance,
+                group: None,
+            })
+            .collect()
+    }
+
+    fn weights(entries: &[Entry]) -> Vec<u64> {
+        entries
+            .iter()
+            .map(|entry| entry.end - entry.start)
+            .collect()
+    }
+
+    #[test]
+    fn test_weight() {
+        assert_eq!(WeightPolicy::Linear.weight(1000), 1000);
+        assert_eq!(WeightPolicy::Sqrt.weight(1), 1);
+        assert_eq!(WeightPolicy::Sqrt.weight(99), 9);
+        assert_eq!(WeightPolicy::Sqrt.weight(100), 10);
+        assert_eq!(WeightPolicy::Capped(50).weight(49), 49);
+        assert_eq!(WeightPolicy::Capped(50).weight(51), 50);
+        assert_eq!(WeightPolicy::Log2.weight(1), 1);
+        assert_eq!(WeightPolicy::Log2.weight(2), 2);
+        assert_eq!(WeightPolicy::Log2.weight(3), 2);
+        assert_eq!(WeightPolicy::Log2.weight(1024), 11);
+        assert_eq!(WeightPolicy::Log2.weight(u64::MAX), 64);
+    }
+
+    #[test]
+    fn test_derive_entries() {
+        let balances = balances(&[1, 16, 100, 10_000]);
+        assert_eq!(
+            weights(&derive_entries(&balances, WeightPolicy::Linear)),
+            vec![1, 16, 100, 10_000]
+        );
+        assert_eq!(
+            weights(&derive_entries(&balances, WeightPolicy::Sqrt)),
+            vec![1, 4, 10, 100]
+        );
+        assert_eq!(
+            weights(&derive_entries(&balances, WeightPolicy::Capped(50))),
+            vec![1, 16, 50, 50]
+        );
+        assert_eq!(
+            weights(&derive_entries(&balances, WeightPolicy::Log2)),
+            vec![1, 5, 7, 14]
+        );
+
+        // Ranges are adjacent and start at 0
+        let entries = derive_entries(&balances, WeightPolicy::Sqrt);
+        assert_eq!(entries[0].start, 0);
+        for pair in entries.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+        assert_eq!(entries.last().unwrap().end, 115);
+    }
+
+    #[test]
+    #[should_panic(expected = "zero balance")]
+    fn test_derive_entries_asserts_balance_nonzero() {
+        derive_entries(&balances(&[10, 0]), WeightPolicy::Linear);
+    }
+
+    #[test]
+    #[should_panic(expected = "cap must be positive")]
+    fn test_derive_entries_asserts_cap_nonzero() {
+        derive_entries(&balances(&[10, 20]), WeightPolicy::Capped(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "total weight overflow")]
+    fn test_derive_entries_asserts_no_overflow() {
+        derive_entries(&balances(&[u64::MAX, 1]), WeightPolicy::Linear);
+    }
+}
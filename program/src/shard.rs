@@ -0,0 +1,328 @@
+// Entries split into contiguous shards, each validated by its own proof of the shard program, and
+// aggregated by the aggregation program, which verifies the shard proofs recursively, checks that
+// consecutive shards continue each other and combines their roots
+// Every shard but the last holds the same power-of-two number of entries, so that the shard roots
+// are the nodes of the commitment tree at that height and the combined root is exactly the one
+// `get_commitment_root` computes over all the entries. Only positional (keccak256, Poseidon and
+// SHA-256) and tagged trees, whose nodes do not depend on the leaves of other shards, can be
+// sharded.
+use crate::{
+    merkle::{leaf::entry_leaf, tagged, Registration, RootBuilder, TreeKind},
+    raffle::{
+        index::{check_entry, InvalidEntries},
+        Entry,
+    },
+};
+use alloy_primitives::Address;
+use alloy_sol_types::{sol, SolType};
+use serde::{Deserialize, Serialize};
+
+pub type ShardPublicValuesTuple = sol! {
+    // shard_root, first_index, entry_count, first_start, last_end, first_address, last_address,
+    // commitment_tree, commitment_leaf_encoding
+    tuple(bytes32,uint64,uint64,uint64,uint64,address,address,uint8,uint8)
+};
+
+pub type AggregationPublicValuesTuple = sol! {
+    // commitment_root, total_weight, entry_count, commitment_tree, commitment_leaf_encoding,
+    // shard_vkey (see `shard_vkey_bytes`), shard_count
+    tuple(bytes32,uint64,uint64,uint8,uint8,bytes32,uint64)
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ShardProgramInput {
+    // Position of the first entry of the shard in the whole list
+    pub first_index: u64,
+    pub entries: Vec<Entry>,
+    pub commitment_tree: TreeKind,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AggregationProgramInput {
+    // Verification key digest of the shard program (`HashableKey::hash_u32`)
+    pub shard_vkey: [u32; 8],
+    // Public values committed by each shard proof, in entry order
+    pub shards: Vec<Vec<u8>>,
+    pub commitment_tree: TreeKind,
+}
+
+// What a shard proof commits about its entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardSummary {
+    // Root of the subtree over the shard's leaves (below the root hash of tagged trees)
+    pub shard_root: [u8; 32],
+    pub first_index: u64,
+    pub entry_count: u64,
+    pub first_start: u64,
+    pub last_end: u64,
+    pub first_address: [u8; 20],
+    pub last_address: [u8; 20],
+    pub commitment_tree: u8,
+    pub leaf_encoding: u8,
+}
+
+impl ShardSummary {
+    pub fn abi_encode(&self) -> Vec<u8> {
+        ShardPublicValuesTuple::abi_encode(&(
+            self.shard_root,
+            self.first_index,
+            self.entry_count,
+            self.first_start,
+            self.last_end,
+            Address::from(self.first_address),
+            Address::from(self.last_address),
+            self.commitment_tree,
+            self.leaf_encoding,
+        ))
+    }
+
+    pub fn abi_decode(bytes: &[u8]) -> Self {
+        let (
+            shard_root,
+            first_index,
+            entry_count,
+            first_start,
+            last_end,
+            first_address,
+            last_address,
+            commitment_tree,
+            leaf_encoding,
+        ) = ShardPublicValuesTuple::abi_decode(bytes, true).expect("invalid shard public values");
+        ShardSummary {
+            shard_root: shard_root.0,
+            first_index,
+            entry_count,
+            first_start,
+            last_end,
+            first_address: first_address.into(),
+            last_address: last_address.into(),
+            commitment_tree,
+            leaf_encoding,
+        }
+    }
+}
+
+// Shard vkey committed by the aggregation program: the BabyBear digest words of the shard
+// program's verification key (`HashableKey::hash_u32`, as verified by `verify_sp1_proof`), each
+// packed big-endian. This is not the BN254 digest returned by `HashableKey::bytes32`.
+pub fn shard_vkey_bytes(words: &[u32; 8]) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    for (chunk, word) in bytes.chunks_mut(4).zip(words) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    bytes
+}
+
+fn assert_shardable(kind: TreeKind) {
+    assert!(
+        matches!(
+            kind,
+            TreeKind::Positional(_)
+                | TreeKind::Tagged(_)
+                | TreeKind::Poseidon
+                | TreeKind::Sha256(_)
+        ),
+        "only positional, tagged, poseidon and sha256 trees can be sharded"
+    );
+}
+
+// Validate the entries of a shard (see `get_commitment_leaves`) and compute the root of their
+// subtree; the first entry is only checked against the previous shard by the aggregation
+pub fn summarize_shard(first_index: u64, entries: &[Entry], kind: TreeKind) -> ShardSummary {
+    assert_shardable(kind);
+    assert!(!entries.is_empty(), "empty shard");
+
+    let mut builder = RootBuilder::new(kind).unwrap();
+    let mut last_entry = None;
+    for (i, entry) in entries.iter().enumerate() {
+        check_entry(first_index as usize + i, last_entry, entry)
+            .unwrap_or_else(|err| panic!("{err}"));
+        builder.push(kind.hash_leaf(&entry_leaf(entry)));
+        last_entry = Some(entry);
+    }
+
+    let (first, last) = (&entries[0], &entries[entries.len() - 1]);
+    ShardSummary {
+        shard_root: builder.tree_root(),
+        first_index,
+        entry_count: entries.len() as u64,
+        first_start: first.start,
+        last_end: last.end,
+        first_address: first.address,
+        last_address: last.address,
+        commitment_tree: kind.id(),
+        leaf_encoding: kind.encoding().id(),
+    }
+}
+
+// Check that the shards cover the whole list of entries and combine their roots into the
+// commitment root, as committed by register mode
+pub fn aggregate_shards(shards: &[ShardSummary], kind: TreeKind) -> Registration {
+    assert_shardable(kind);
+    assert!(!shards.is_empty(), "no shards");
+
+    let shard_size = shards[0].entry_count;
+    let mut builder = RootBuilder::new(kind).unwrap();
+    for (i, shard) in shards.iter().enumerate() {
+        assert!(
+            shard.commitment_tree == kind.id() && shard.leaf_encoding == kind.encoding().id(),
+            "shard built with another tree kind (shard {i})"
+        );
+        if i + 1 < shards.len() {
+            assert!(
+                shard.entry_count == shard_size && shard_size.is_power_of_two(),
+                "every shard but the last must hold the same power-of-two number of entries \
+                 (shard {i})"
+            );
+        } else {
+            assert!(
+                shard.entry_count <= shard_size,
+                "the last shard must not hold more entries than the others"
+            );
+        }
+        match i.checked_sub(1).map(|j| &shards[j]) {
+            // The shard proof checked that entry 0 starts at 0
+            None => assert!(shard.first_index == 0, "first shard must start at entry 0"),
+            Some(previous) => {
+                assert!(
+                    shard.first_index == previous.first_index + previous.entry_count,
+                    "non-contiguous shards (shard {i})"
+                );
+                assert!(
+                    shard.first_start == previous.last_end,
+                    "non-adjacent entries (shard {i})"
+                );
+                assert!(
+                    previous.last_address < shard.first_address,
+                    "entries must be ordered (asc) by addresses (shard {i})"
+                );
+            }
+        }
+        builder.push(shard.shard_root);
+    }
+
+    let last = &shards[shards.len() - 1];
+    let entry_count = last.first_index + last.entry_count;
+    if entry_count < 2 {
+        panic!("{}", InvalidEntries::TooFew);
+    }
+    let tree_root = builder.tree_root();
+    Registration {
+        commitment_root: match kind {
+            TreeKind::Tagged(_) => tagged::hash_root(entry_count, &tree_root),
+            _ => tree_root,
+        },
+        total_weight: last.last_end,
+        entry_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        merkle::{leaf::LeafEncoding, register_entries},
+        test_util::entries,
+    };
+
+    fn summarize(entries: &[Entry], shard_size: usize, kind: TreeKind) -> Vec<ShardSummary> {
+        entries
+            .chunks(shard_size)
+            .enumerate()
+            .map(|(i, shard)| summarize_shard((i * shard_size) as u64, shard, kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_aggregated_root_matches_commitment_root() {
+        for kind in [
+            TreeKind::Positional(LeafEncoding::Packed),
+            TreeKind::Tagged(LeafEncoding::Abi),
+            TreeKind::Poseidon,
+            TreeKind::Sha256(LeafEncoding::Packed),
+        ] {
+            for n in [2, 3, 7, 8, 9, 31, 40] {
+                let entries = entries(&vec![5; n]);
+                for shard_size in [1, 2, 4, 8, 64] {
+                    let shards = summarize(&entries, shard_size, kind);
+                    assert_eq!(
+                        aggregate_shards(&shards, kind),
+                        register_entries(&entries, kind),
+                        "{kind:?}, {n} entries in shards of {shard_size}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shard_public_values() {
+        let kind = TreeKind::Tagged(LeafEncoding::Packed);
+        let summary = summarize_shard(4, &entries(&[5; 12])[4..8], kind);
+        assert_eq!(ShardSummary::abi_decode(&summary.abi_encode()), summary);
+        assert_eq!(
+            (summary.first_start, summary.last_end),
+            (20, 40),
+            "shard covers tickets [20, 40)"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "non-adjacent entries (shard 1)")]
+    fn test_aggregate_rejects_gaps() {
+        let kind = TreeKind::Positional(LeafEncoding::Packed);
+        let mut entries = entries(&[5; 8]);
+        for entry in &mut entries[4..] {
+            entry.start += 1;
+            entry.end += 1;
+        }
+        aggregate_shards(&summarize(&entries, 4, kind), kind);
+    }
+
+    #[test]
+    #[should_panic(expected = "entries must be ordered (asc) by addresses (shard 1)")]
+    fn test_aggregate_rejects_unordered_shards() {
+        let kind = TreeKind::Positional(LeafEncoding::Packed);
+        let entries = entries(&[5; 8]);
+        let mut shards = summarize(&entries, 4, kind);
+        shards[1].first_address = shards[0].last_address;
+        aggregate_shards(&shards, kind);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-contiguous shards (shard 2)")]
+    fn test_aggregate_rejects_missing_shard() {
+        let kind = TreeKind::Positional(LeafEncoding::Packed);
+        let mut shards = summarize(&entries(&[5; 16]), 4, kind);
+        shards.remove(2);
+        aggregate_shards(&shards, kind);
+    }
+
+    #[test]
+    #[should_panic(expected = "every shard but the last must hold the same power-of-two number")]
+    fn test_aggregate_rejects_unaligned_shards() {
+        let kind = TreeKind::Positional(LeafEncoding::Packed);
+        aggregate_shards(&summarize(&entries(&[5; 9]), 3, kind), kind);
+    }
+
+    #[test]
+    fn test_shard_vkey_bytes() {
+        let bytes = shard_vkey_bytes(&[1, 2, 3, 4, 5, 6, 7, 0x0a0b0c0d]);
+        assert_eq!(&bytes[..8], &[0, 0, 0, 1, 0, 0, 0, 2]);
+        assert_eq!(&bytes[28..], &[0x0a, 0x0b, 0x0c, 0x0d]);
+    }
+
+    #[test]
+    #[should_panic(expected = "first entry must start at 0")]
+    fn test_first_shard_starts_at_zero() {
+        let mut entries = entries(&[5; 4]);
+        entries[0].start = 1;
+        summarize_shard(0, &entries, TreeKind::Positional(LeafEncoding::Packed));
+    }
+
+    #[test]
+    #[should_panic(expected = "only positional, tagged, poseidon and sha256 trees can be sharded")]
+    fn test_openzeppelin_trees_cannot_be_sharded() {
+        summarize_shard(0, &entries(&[5; 4]), TreeKind::OpenZeppelin);
+    }
+}
@@ -0,0 +1,46 @@
+// Fixtures shared by the unit tests
+use crate::raffle::Entry;
+
+// Consecutive entries with the given weights, owned by addresses [1; 20], [2; 20], ...
+pub fn entries(weights: &[u64]) -> Vec<Entry> {
+    grouped_entries(weights, &vec![None; weights.len()])
+}
+
+// Consecutive entries with the given weights and groups, owned by addresses [1; 20], [2; 20], ...
+pub fn grouped_entries(weights: &[u64], groups: &[Option<u32>]) -> Vec<Entry> {
+    assert_eq!(weights.len(), groups.len());
+    let mut start = 0;
+    weights
+        .iter()
+        .zip(groups)
+        .enumerate()
+        .map(|(i, (weight, &group))| {
+            let entry = Entry {
+                address: [i as u8 + 1; 20],
+                start,
+                end: start + weight,
+                group,
+            };
+            start = entry.end;
+            entry
+        })
+        .collect()
+}
+
+// Lowercase hex encoding of the bytes, without a 0x prefix
+pub fn hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes.iter().fold(String::new(), |mut out, byte| {
+        write!(out, "{:02x}", byte).unwrap();
+        out
+    })
+}
+
+// Parse a 0x-prefixed 32-byte hex string
+pub fn from_hex(s: &str) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[2 + 2 * i..4 + 2 * i], 16).unwrap();
+    }
+    out
+}
@@ -0,0 +1,424 @@
+//! Public values committed by the weighted raffle program, and a typed view of them for the
+//! contracts' off-chain consumers (indexers, backends) and the script.
+//!
+//! This crate only depends on alloy, so that consumers do not pull in the program and its zkVM
+//! dependencies.
+//!
+//! The public values of the original program, (commitRoot, seed, winnersRoot), are a prefix of the
+//! current layout. They are decoded as version 0, with the fields they lack zeroed, which are the
+//! defaults the original program used (winners mode, positional trees, packed leaves, no quotas, no
+//! raffle binding).
+use alloy_primitives::{Address, B256};
+use alloy_sol_types::{sol, sol_data, SolType};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Version of the public values and of the draw algorithm, bumped whenever either changes.
+pub const VERSION: u8 = 1;
+
+sol! {
+    /// Public values committed by the program, abi-encoded as a tuple of its (static) fields so
+    /// that contracts can decode them with `abi.decode(publicValues, (PublicValuesStruct))`.
+    ///
+    /// The public values of the original program, `(commitmentRoot, seed, winnersRoot)`, are a
+    /// prefix of these.
+    #[derive(Debug, PartialEq, Eq)]
+    struct PublicValuesStruct {
+        bytes32 commitmentRoot;
+        /// Seed of the raffle, before it is bound to the raffle (see `raffle::bind_seed` in the
+        /// program).
+        bytes32 seed;
+        /// Ranking root in ranking mode, zero in register mode.
+        bytes32 winnersRoot;
+        /// Zero in sublinear and register modes.
+        bytes32 balancesRoot;
+        uint8 weightPolicy;
+        uint64 weightParam;
+        /// Zero without quotas (see `raffle::quotas_hash` in the program).
+        bytes32 quotasHash;
+        uint8 mode;
+        uint8 commitmentTree;
+        uint8 winnersTree;
+        uint8 commitmentLeafEncoding;
+        uint8 winnersLeafEncoding;
+        uint64 totalWeight;
+        uint64 entryCount;
+        /// Number of leaves of the winners (or ranking) tree, zero in register mode.
+        uint64 numWinners;
+        uint8 version;
+        /// Rounds of the Feistel network permuting the tickets.
+        uint8 rounds;
+        uint64 raffleId;
+        uint64 chainId;
+        address raffleContract;
+        /// keccak256 of the canonical encoding of the program input (see `fingerprint` in the
+        /// program).
+        bytes32 inputHash;
+    }
+}
+
+/// Public values committed by the program: `abi.encode(PublicValuesStruct, address[])`, where the
+/// array holds the winners (or the ranking) in draw order if they are committed inline (see
+/// `WeightedRaffleProgramInput::inline_winners` in the program), and is empty otherwise.
+///
+/// The struct comes first so that the public values of the original program remain a prefix.
+pub type PublicValuesTuple = (PublicValuesStruct, sol_data::Array<sol_data::Address>);
+
+/// Id committed in `mode` by winners draws (see `RaffleMode` in the program).
+pub const WINNERS_MODE: u8 = 0;
+/// Id committed in `mode` by rankings.
+pub const RANKING_MODE: u8 = 1;
+/// Id committed in `mode` by sublinear draws.
+pub const SUBLINEAR_MODE: u8 = 2;
+/// Id committed in `mode` by registrations, which draw nothing.
+pub const REGISTER_MODE: u8 = 3;
+
+/// Feistel rounds of the original program, which did not commit them.
+pub const LEGACY_ROUNDS: u8 = 4;
+
+// Size (in bytes) of the public values of the original program
+const LEGACY_SIZE: usize = 3 * 32;
+// Position of the version field
+const VERSION_WORD: usize = 15;
+
+/// Why public values could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicValuesError {
+    /// The size (in bytes) matches no layout.
+    UnknownLayout(usize),
+    /// The version is newer than this crate.
+    UnsupportedVersion(u8),
+    /// The values do not abi-decode.
+    Invalid(String),
+}
+
+impl fmt::Display for PublicValuesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PublicValuesError::UnknownLayout(size) => {
+                write!(f, "unknown public values layout ({size} bytes)")
+            }
+            PublicValuesError::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {version} (latest is {VERSION})")
+            }
+            PublicValuesError::Invalid(err) => write!(f, "invalid public values: {err}"),
+        }
+    }
+}
+
+impl From<alloy_sol_types::Error> for PublicValuesError {
+    fn from(err: alloy_sol_types::Error) -> Self {
+        PublicValuesError::Invalid(err.to_string())
+    }
+}
+
+/// Detect the version of the program that committed the given public values, 0 for the original
+/// program.
+pub fn detect_version(bytes: &[u8]) -> Result<u8, PublicValuesError> {
+    if bytes.len() == LEGACY_SIZE {
+        return Ok(0);
+    }
+    let unknown = || PublicValuesError::UnknownLayout(bytes.len());
+    // Whole words (`usize::is_multiple_of` is newer than the pinned toolchain), at least the
+    // struct, the offset of the array and its length
+    let whole_words = bytes.chunks_exact(32).remainder().is_empty();
+    if !whole_words || bytes.len() < PublicValuesStruct::ENCODED_SIZE.unwrap() + 2 * 32 {
+        return Err(unknown());
+    }
+    let word = &bytes[VERSION_WORD * 32..(VERSION_WORD + 1) * 32];
+    if word[..31].iter().any(|byte| *byte != 0) {
+        return Err(unknown());
+    }
+    match word[31] {
+        VERSION => Ok(VERSION),
+        version if version > VERSION => Err(PublicValuesError::UnsupportedVersion(version)),
+        _ => Err(unknown()),
+    }
+}
+
+/// The public values of any version of the program, with named fields.
+///
+/// Serialized with camelCase keys, as in the proof fixtures.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RafflePublicValues {
+    /// Version of the layout and draw algorithm, 0 for the original program.
+    pub version: u8,
+    /// Root of the entries' commitment tree, named `commitRoot` as in the earlier fixtures.
+    #[serde(rename = "commitRoot")]
+    pub commitment_root: B256,
+    /// Seed of the raffle, before it is bound to the raffle.
+    pub seed: B256,
+    /// Root of the winners tree, of the ranking tree in ranking mode, zero in register mode.
+    pub winners_root: B256,
+    /// Root of the balances tree, zero in sublinear and register modes.
+    pub balances_root: B256,
+    /// Id of the weight policy deriving the entries' weights from their balances.
+    pub weight_policy: u8,
+    /// Parameter of the weight policy.
+    pub weight_param: u64,
+    /// keccak256 of the abi-encoded winner quotas, zero without quotas.
+    pub quotas_hash: B256,
+    /// Id of the mode (see `WINNERS_MODE`, `RANKING_MODE`, `SUBLINEAR_MODE` and `REGISTER_MODE`).
+    pub mode: u8,
+    /// Id of the layout of the commitment and balances trees.
+    pub commitment_tree: u8,
+    /// Id of the layout of the winners or ranking tree.
+    pub winners_tree: u8,
+    /// Id of the leaf encoding of the commitment and balances trees.
+    pub commitment_leaf_encoding: u8,
+    /// Id of the leaf encoding of the winners or ranking tree.
+    pub winners_leaf_encoding: u8,
+    /// Total weight of the entries.
+    pub total_weight: u64,
+    /// Number of entries.
+    pub entry_count: u64,
+    /// Number of leaves of the winners (or ranking) tree, zero in register mode.
+    pub num_winners: u64,
+    /// Rounds of the Feistel network permuting the tickets.
+    pub rounds: u8,
+    /// Id of the raffle the draw is bound to.
+    pub raffle_id: u64,
+    /// Chain of the raffle contract.
+    pub chain_id: u64,
+    /// Contract of the raffle.
+    pub raffle_contract: Address,
+    /// keccak256 of the canonical encoding of the program input.
+    pub input_hash: B256,
+    /// Winners (or ranking) committed inline, in draw order, empty if they are not.
+    pub winners: Vec<Address>,
+}
+
+impl RafflePublicValues {
+    /// Decode the public values committed by the program, or those of the original program, with
+    /// the fields it did not commit zeroed.
+    pub fn decode(bytes: &[u8]) -> Result<Self, PublicValuesError> {
+        let version = detect_version(bytes)?;
+        let (values, winners) = if version == 0 {
+            // Fields the original program did not commit are zero
+            let mut padded = bytes.to_vec();
+            padded.resize(PublicValuesStruct::ENCODED_SIZE.unwrap(), 0);
+            let mut values = PublicValuesStruct::abi_decode(&padded, true)?;
+            values.rounds = LEGACY_ROUNDS;
+            (values, vec![])
+        } else {
+            PublicValuesTuple::abi_decode_params(bytes, true)?
+        };
+        Ok(RafflePublicValues {
+            version,
+            commitment_root: values.commitmentRoot,
+            seed: values.seed,
+            winners_root: values.winnersRoot,
+            balances_root: values.balancesRoot,
+            weight_policy: values.weightPolicy,
+            weight_param: values.weightParam,
+            quotas_hash: values.quotasHash,
+            mode: values.mode,
+            commitment_tree: values.commitmentTree,
+            winners_tree: values.winnersTree,
+            commitment_leaf_encoding: values.commitmentLeafEncoding,
+            winners_leaf_encoding: values.winnersLeafEncoding,
+            total_weight: values.totalWeight,
+            entry_count: values.entryCount,
+            num_winners: values.numWinners,
+            rounds: values.rounds,
+            raffle_id: values.raffleId,
+            chain_id: values.chainId,
+            raffle_contract: values.raffleContract,
+            input_hash: values.inputHash,
+            winners,
+        })
+    }
+
+    /// Encode the public values in the current layout, as the program commits them.
+    pub fn abi_encode(&self) -> Vec<u8> {
+        let values = PublicValuesStruct {
+            commitmentRoot: self.commitment_root,
+            seed: self.seed,
+            winnersRoot: self.winners_root,
+            balancesRoot: self.balances_root,
+            weightPolicy: self.weight_policy,
+            weightParam: self.weight_param,
+            quotasHash: self.quotas_hash,
+            mode: self.mode,
+            commitmentTree: self.commitment_tree,
+            winnersTree: self.winners_tree,
+            commitmentLeafEncoding: self.commitment_leaf_encoding,
+            winnersLeafEncoding: self.winners_leaf_encoding,
+            totalWeight: self.total_weight,
+            entryCount: self.entry_count,
+            numWinners: self.num_winners,
+            version: self.version,
+            rounds: self.rounds,
+            raffleId: self.raffle_id,
+            chainId: self.chain_id,
+            raffleContract: self.raffle_contract,
+            inputHash: self.input_hash,
+        };
+        PublicValuesTuple::abi_encode_params(&(values, self.winners.clone()))
+    }
+}
+
+impl fmt::Display for RafflePublicValues {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Version: {} ({} Feistel rounds)",
+            self.version, self.rounds
+        )?;
+        writeln!(f, "Commitment root: {}", self.commitment_root)?;
+        writeln!(f, "Seed: {}", self.seed)?;
+        if self.mode == REGISTER_MODE {
+            writeln!(f, "Registered entries (no draw)")?;
+        } else if self.mode == RANKING_MODE {
+            writeln!(f, "Ranking root: {}", self.winners_root)?;
+        } else {
+            writeln!(
+                f,
+                "Winners root: {} ({} winners)",
+                self.winners_root, self.num_winners
+            )?;
+        }
+        writeln!(f, "Balances root: {}", self.balances_root)?;
+        writeln!(
+            f,
+            "Weight policy: {} (param: {})",
+            self.weight_policy, self.weight_param
+        )?;
+        writeln!(f, "Quotas hash: {}", self.quotas_hash)?;
+        writeln!(
+            f,
+            "Tree kinds: {} (commitment), {} (winners)",
+            self.commitment_tree, self.winners_tree
+        )?;
+        writeln!(
+            f,
+            "Leaf encodings: {} (commitment), {} (winners)",
+            self.commitment_leaf_encoding, self.winners_leaf_encoding
+        )?;
+        writeln!(
+            f,
+            "Total weight: {} ({} entries)",
+            self.total_weight, self.entry_count
+        )?;
+        writeln!(
+            f,
+            "Raffle: {} of contract {} on chain {}",
+            self.raffle_id, self.raffle_contract, self.chain_id
+        )?;
+        write!(f, "Input hash: {}", self.input_hash)?;
+        for (i, winner) in self.winners.iter().enumerate() {
+            write!(f, "\nWinner #{i}: {winner}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> RafflePublicValues {
+        RafflePublicValues {
+            version: VERSION,
+            commitment_root: B256::repeat_byte(1),
+            seed: B256::repeat_byte(2),
+            winners_root: B256::repeat_byte(3),
+            balances_root: B256::repeat_byte(4),
+            weight_policy: 2,
+            weight_param: 50,
+            quotas_hash: B256::repeat_byte(7),
+            mode: WINNERS_MODE,
+            commitment_tree: 2,
+            winners_tree: 0,
+            commitment_leaf_encoding: 1,
+            winners_leaf_encoding: 0,
+            total_weight: 1000,
+            entry_count: 100,
+            num_winners: 2,
+            rounds: LEGACY_ROUNDS,
+            raffle_id: 7,
+            chain_id: 31337,
+            raffle_contract: Address::repeat_byte(5),
+            input_hash: B256::repeat_byte(6),
+            winners: vec![Address::repeat_byte(8), Address::repeat_byte(9)],
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let values = values();
+        let bytes = values.abi_encode();
+        assert_eq!(detect_version(&bytes), Ok(VERSION));
+        assert_eq!(RafflePublicValues::decode(&bytes), Ok(values.clone()));
+
+        let values = RafflePublicValues {
+            winners: vec![],
+            ..values
+        };
+        assert_eq!(RafflePublicValues::decode(&values.abi_encode()), Ok(values));
+    }
+
+    #[test]
+    fn test_decode_original_program() {
+        // Original (commitRoot, seed, winnersRoot) layout
+        let bytes = values().abi_encode();
+        let legacy = RafflePublicValues::decode(&bytes[..96]).unwrap();
+        assert_eq!(legacy.version, 0);
+        assert_eq!(legacy.winners_root, B256::repeat_byte(3));
+        assert_eq!(legacy.balances_root, B256::ZERO);
+        assert_eq!(legacy.quotas_hash, B256::ZERO);
+        assert_eq!(legacy.rounds, LEGACY_ROUNDS);
+        assert!(legacy.winners.is_empty());
+    }
+
+    #[test]
+    fn test_detect_version_rejects_unknown_layouts() {
+        let bytes = values().abi_encode();
+        assert_eq!(
+            detect_version(&bytes[..4 * 32]),
+            Err(PublicValuesError::UnknownLayout(128))
+        );
+        assert_eq!(
+            detect_version(&bytes[..95]),
+            Err(PublicValuesError::UnknownLayout(95))
+        );
+
+        let mut future = bytes.clone();
+        future[VERSION_WORD * 32 + 31] = VERSION + 1;
+        assert_eq!(
+            detect_version(&future),
+            Err(PublicValuesError::UnsupportedVersion(VERSION + 1))
+        );
+        assert!(matches!(
+            RafflePublicValues::decode(&bytes[..bytes.len() - 32]),
+            Err(PublicValuesError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_serde_keys() {
+        let json = serde_json::to_value(values()).unwrap();
+        // Same keys as the fixtures written before this struct
+        for key in [
+            "commitRoot",
+            "seed",
+            "winnersRoot",
+            "balancesRoot",
+            "inputHash",
+        ] {
+            assert!(json.get(key).is_some(), "missing {key}");
+        }
+        assert_eq!(
+            serde_json::from_value::<RafflePublicValues>(json).unwrap(),
+            values()
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let display = values().to_string();
+        assert!(display.starts_with(&format!("Version: {VERSION} (4 Feistel rounds)")));
+        assert!(display.ends_with(&format!("Winner #1: {}", Address::repeat_byte(9))));
+    }
+}
@@ -1,5 +1,8 @@
 use sp1_helper::build_program;
 
 fn main() {
-    build_program("../program")
+    build_program("../program");
+    build_program("../bench-program");
+    build_program("../shard-program");
+    build_program("../aggregation-program");
 }
@@ -0,0 +1,140 @@
+//! A script to compare the memory and cycles of computing the commitment root with the streaming
+//! root builder against collecting the leaves and building the full rs_merkle tree.
+//!
+//! Memory and time are measured on the host for both builders. With `--execute`, the bench guest
+//! in `bench-program` is also executed in the zkVM on every input size with each builder, to report
+//! the cycles of the `commitment-root` cycle tracker inside the guest.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package weighted-raffle-script --bin bench --release -- --sizes 1000,100000,1000000
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use sp1_sdk::{ProverClient, SP1Stdin};
+use weighted_raffle_program::{
+    bench::{get_materialized_commitment_root, BenchProgramInput},
+    merkle::{get_commitment_root, leaf::LeafEncoding, TreeKind},
+    raffle::weight::derive_entries,
+    RaffleMode,
+};
+use weighted_raffle_script::sample_input_of_size;
+
+/// The ELF (executable and linkable format) file of the bench guest for the Succinct RISC-V zkVM.
+pub const BENCH_ELF: &[u8] =
+    include_bytes!("../../../bench-program/elf/riscv32im-succinct-zkvm-elf");
+
+// Tracks the current and peak number of bytes allocated on the heap.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// The arguments for the bench command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct BenchArgs {
+    /// The numbers of entries to benchmark, comma-separated.
+    #[clap(
+        long,
+        value_delimiter = ',',
+        default_value = "1000,10000,100000,1000000"
+    )]
+    sizes: Vec<u64>,
+
+    /// Also execute the bench guest in the zkVM with both builders to report cycles.
+    #[clap(long, default_value = "false")]
+    execute: bool,
+}
+
+/// Run `f`, returning its result, the peak heap it allocated on top of what was already allocated,
+/// and its duration.
+fn measure<T>(f: impl FnOnce() -> T) -> (T, usize, Duration) {
+    let baseline = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    (result, PEAK.load(Ordering::SeqCst) - baseline, elapsed)
+}
+
+/// Execute the bench guest in the zkVM, returning the cycles of the commitment root.
+fn execute(input: &BenchProgramInput) -> u64 {
+    let mut stdin = SP1Stdin::new();
+    stdin.write(input);
+    let (_, report) = ProverClient::new().execute(BENCH_ELF, stdin).run().unwrap();
+    *report
+        .cycle_tracker
+        .get("commitment-root")
+        .expect("missing commitment-root cycle tracker")
+}
+
+fn main() {
+    sp1_sdk::utils::setup_logger();
+    let args = BenchArgs::parse();
+    let kind = TreeKind::Positional(LeafEncoding::Packed);
+
+    println!(
+        "{:>10} | {:>14} {:>10} {:>12} | {:>14} {:>10} {:>12}",
+        "entries", "legacy heap", "time", "cycles", "streaming heap", "time", "cycles"
+    );
+    for size in args.sizes {
+        let input = sample_input_of_size(size, RaffleMode::Winners, kind);
+        let entries = derive_entries(&input.balances, input.weight_policy);
+
+        let (legacy, legacy_heap, legacy_time) =
+            measure(|| get_materialized_commitment_root(&entries, kind));
+        let (streaming, streaming_heap, streaming_time) =
+            measure(|| get_commitment_root(&entries, kind));
+        assert_eq!(legacy, streaming, "roots differ for {size} entries");
+
+        let (legacy_cycles, streaming_cycles) = if args.execute {
+            let mut input = BenchProgramInput {
+                entries,
+                commitment_tree: kind,
+                materialized: true,
+            };
+            let legacy = execute(&input).to_string();
+            input.materialized = false;
+            (legacy, execute(&input).to_string())
+        } else {
+            ("-".to_string(), "-".to_string())
+        };
+
+        println!(
+            "{:>10} | {:>14} {:>10.2?} {:>12} | {:>14} {:>10.2?} {:>12}",
+            size,
+            legacy_heap,
+            legacy_time,
+            legacy_cycles,
+            streaming_heap,
+            streaming_time,
+            streaming_cycles
+        );
+    }
+}
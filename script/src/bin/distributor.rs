@@ -0,0 +1,162 @@
+//! A script to export the winners as a claims file for Uniswap's merkle-distributor, where the i-th
+//! winner drawn is entitled to the i-th prize of the given tiers.
+//!
+//! The claims root is not committed by the program, so the winners it is built from are checked
+//! against the winners root the program commits: the script prints the winners root of the input,
+//! and with `--fixture` asserts that the proof fixture was made from that input and commits that
+//! root before writing the claims.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package weighted-raffle-script --bin distributor --release -- \
+//!     --tier 1:1000 --tier 3:500 --tier 6:100 --output claims.json
+//! ```
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use alloy_primitives::{Address, B256};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use weighted_raffle_program::{
+    fingerprint::input_hash,
+    merkle::{
+        distributor, get_claim_root, get_drawn_winners_root, leaf::LeafEncoding,
+        proof::get_claim_proofs, TreeKind,
+    },
+    raffle::{draw, weight::derive_entries},
+    RaffleMode,
+};
+use weighted_raffle_public_values::RafflePublicValues;
+use weighted_raffle_script::{parse_tier, prize_amounts, sample_input, to_hex, InputArgs};
+
+/// The arguments for the distributor command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct DistributorArgs {
+    /// A prize tier as COUNT:AMOUNT, repeated in draw order. Use a count of 1 per prize to set
+    /// every prize individually.
+    #[clap(long = "tier", value_parser = parse_tier, required = true)]
+    tiers: Vec<(u64, u128)>,
+
+    /// Write the claims to this file instead of stdout.
+    #[clap(long)]
+    output: Option<PathBuf>,
+
+    /// The proof fixture written by `prove --evm` for the input, whose input hash and winners root
+    /// must match the input and its winners before the claims are written.
+    #[clap(long)]
+    fixture: Option<PathBuf>,
+
+    #[clap(flatten)]
+    input: InputArgs,
+}
+
+/// The claim of a winner and the proof of its leaf in the claims tree.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Claim {
+    index: u64,
+    amount: String,
+    proof: Vec<String>,
+}
+
+/// The public values of a proof fixture, as written by `prove --evm`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Fixture {
+    public_values: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Distribution {
+    merkle_root: String,
+    token_total: String,
+    claims: BTreeMap<String, Claim>,
+}
+
+fn main() {
+    let args = DistributorArgs::parse();
+
+    // Draw the winners exactly as the program does in winners mode. The claims tree is the
+    // distributor's own, whatever the winners tree committed by the program.
+    let input = args.input.read().unwrap_or_else(|| {
+        sample_input(
+            RaffleMode::Winners,
+            TreeKind::Positional(LeafEncoding::Packed),
+        )
+    });
+    assert_eq!(
+        input.mode,
+        RaffleMode::Winners,
+        "the input does not draw winners"
+    );
+    let entries = derive_entries(&input.balances, input.weight_policy);
+    let winners = draw(
+        input.num_winners,
+        &input.quotas,
+        input.draw_seed(),
+        &entries,
+    );
+
+    // The winners root the program commits for these winners, which proves the claims' winners.
+    let winners_root = get_drawn_winners_root(&winners, input.winners_tree);
+    eprintln!("Winners root: {}", to_hex(winners_root));
+    if let Some(path) = args.fixture {
+        let fixture: Fixture = serde_json::from_str(
+            &std::fs::read_to_string(path).expect("failed to read the fixture"),
+        )
+        .expect("invalid fixture");
+        let bytes = hex::decode(fixture.public_values.trim_start_matches("0x"))
+            .expect("invalid public values");
+        let values = RafflePublicValues::decode(&bytes).unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(
+            values.input_hash,
+            B256::from(input_hash(&input)),
+            "the fixture was not made from this input"
+        );
+        assert_eq!(
+            values.winners_root,
+            B256::from(winners_root),
+            "the fixture does not commit these winners"
+        );
+    }
+
+    let amounts = prize_amounts(&args.tiers, input.num_winners);
+    let token_total = amounts
+        .iter()
+        .copied()
+        .try_fold(0u128, u128::checked_add)
+        .expect("the prizes overflow a uint128 token total");
+
+    let root = get_claim_root(&winners, &amounts);
+    let mut claims = BTreeMap::new();
+    for proof in get_claim_proofs(&winners, &amounts) {
+        assert!(
+            distributor::verify(&root, &proof.leaf, &proof.proof),
+            "invalid proof for {}",
+            to_hex(proof.address)
+        );
+        // merkle-distributor's claim files are keyed by EIP-55 checksummed addresses
+        claims.insert(
+            Address::from(proof.address).to_checksum(None),
+            Claim {
+                index: proof.index,
+                amount: format!("{:#x}", amounts[proof.index as usize]),
+                proof: proof.proof.iter().map(to_hex).collect(),
+            },
+        );
+    }
+
+    let output = Distribution {
+        merkle_root: to_hex(root),
+        token_total: format!("{:#x}", token_total),
+        claims,
+    };
+
+    let json = serde_json::to_string_pretty(&output).unwrap();
+    match args.output {
+        Some(path) => std::fs::write(path, json).expect("failed to write claims"),
+        None => println!("{}", json),
+    }
+}
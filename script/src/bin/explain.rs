@@ -0,0 +1,109 @@
+//! A script to explain whether, and how, an address won the raffle. Only the prefix of the draw
+//! up to the address's win is replayed.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package weighted-raffle-script --bin explain --release -- --address <ADDRESS>
+//! ```
+//!
+//! Pass `--input <json>` to explain the draw of a program input instead of the sample input.
+
+use clap::Parser;
+use weighted_raffle_program::{
+    merkle::{leaf::LeafEncoding, TreeKind},
+    raffle::{explain_draw, weight::derive_entries, DrawStep, Outcome},
+    RaffleMode,
+};
+use weighted_raffle_script::{sample_input, to_hex, InputArgs};
+
+/// The arguments for the explain command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct ExplainArgs {
+    /// The 0x-prefixed address to explain the draw for.
+    #[clap(long)]
+    address: String,
+
+    #[clap(flatten)]
+    input: InputArgs,
+}
+
+fn describe(step: &DrawStep) -> String {
+    format!(
+        "index {} -> ticket {} in [{}, {}) of {}",
+        step.index,
+        step.ticket,
+        step.entry.start,
+        step.entry.end,
+        to_hex(step.entry.address)
+    )
+}
+
+fn main() {
+    let args = ExplainArgs::parse();
+    let mut address = [0u8; 20];
+    hex::decode_to_slice(args.address.trim_start_matches("0x"), &mut address)
+        .expect("invalid address");
+
+    // Replay the draw exactly as the program does.
+    let input = args.input.read().unwrap_or_else(|| {
+        sample_input(
+            RaffleMode::Winners,
+            TreeKind::Positional(LeafEncoding::Packed),
+        )
+    });
+    assert_eq!(
+        input.mode,
+        RaffleMode::Winners,
+        "the input does not draw winners"
+    );
+    let entries = derive_entries(&input.balances, input.weight_policy);
+    if !entries.iter().any(|entry| entry.address == address) {
+        println!("{} is not an entry of the raffle", to_hex(address));
+        return;
+    }
+
+    let Some(explanation) = explain_draw(
+        &address,
+        input.num_winners,
+        &input.quotas,
+        input.draw_seed(),
+        &entries,
+    ) else {
+        println!(
+            "{} is not among the {} winners",
+            to_hex(address),
+            input.num_winners
+        );
+        return;
+    };
+
+    let total_weight = entries.last().unwrap().end;
+    println!(
+        "{} is winner #{} of {} (winners are numbered from 0)",
+        to_hex(address),
+        explanation.winner_index,
+        input.num_winners
+    );
+    println!(
+        "Seed: {} (bound to the raffle: {})",
+        to_hex(input.seed),
+        to_hex(input.draw_seed())
+    );
+    println!("Total weight: {}", total_weight);
+    println!(
+        "Winning {}, the ticket being deshuffle({}, seed, {})",
+        describe(&explanation.winner),
+        explanation.winner.index,
+        total_weight
+    );
+    println!("Skipped {} earlier indices:", explanation.skipped.len());
+    for step in &explanation.skipped {
+        let reason = match step.outcome {
+            Outcome::AlreadyWon => "already won",
+            Outcome::QuotaFilled => "no winners left for its group",
+            Outcome::Winner => unreachable!("winners are never skipped"),
+        };
+        println!("  {}: {}", describe(step), reason);
+    }
+}
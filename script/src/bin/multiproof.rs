@@ -0,0 +1,164 @@
+//! A script to print a single multiproof of several addresses' entries against the commitment root
+//! and of those that won against the winners root, in the format of OpenZeppelin's
+//! `MerkleProof.multiProofVerify(proof, proofFlags, root, leaves)`. Trees are always built as
+//! OpenZeppelin StandardMerkleTrees, so a program input read with `--input <json>` instead of the
+//! sample input must commit OpenZeppelin trees.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package weighted-raffle-script --bin multiproof --release -- \
+//!     --address 0x000000000000000000000000000000000000001b \
+//!     --address 0x00000000000000000000000000000000000000c8
+//! ```
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use serde::Serialize;
+use weighted_raffle_program::{
+    merkle::{
+        get_commitment_root, get_winners_root,
+        proof::{get_commitment_multi_proof, get_winners_multi_proof},
+        standard::{verify_multi_proof, MultiProof},
+        TreeKind,
+    },
+    raffle::{draw, weight::derive_entries},
+    RaffleMode,
+};
+use weighted_raffle_script::{parse_address, sample_input, to_hex, InputArgs};
+
+/// The arguments for the multiproof command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct MultiProofArgs {
+    /// An address to include in the multiproofs, repeated. Defaults to every winner.
+    #[clap(long = "address", value_parser = parse_address)]
+    addresses: Vec<[u8; 20]>,
+
+    /// Write the multiproofs to this file instead of stdout.
+    #[clap(long)]
+    output: Option<PathBuf>,
+
+    #[clap(flatten)]
+    input: InputArgs,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TreeMultiProof {
+    root: String,
+    // Address of each leaf, in the order of `leaves`
+    addresses: Vec<String>,
+    leaves: Vec<String>,
+    proof: Vec<String>,
+    proof_flags: Vec<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MultiProofs {
+    commitment: TreeMultiProof,
+    winners: TreeMultiProof,
+}
+
+fn tree_multi_proof(
+    root: [u8; 32],
+    multi_proof: MultiProof,
+    addresses: &[[u8; 20]],
+) -> TreeMultiProof {
+    assert!(
+        verify_multi_proof(&root, &multi_proof),
+        "invalid multiproof"
+    );
+    TreeMultiProof {
+        root: to_hex(root),
+        addresses: multi_proof
+            .indices
+            .iter()
+            .map(|index| to_hex(addresses[*index]))
+            .collect(),
+        leaves: multi_proof.leaves.iter().map(to_hex).collect(),
+        proof: multi_proof.proof.iter().map(to_hex).collect(),
+        proof_flags: multi_proof.proof_flags,
+    }
+}
+
+fn main() {
+    let args = MultiProofArgs::parse();
+
+    // Rebuild the commitment and winners trees exactly as the program does in winners mode.
+    let kind = TreeKind::OpenZeppelin;
+    let input = args
+        .input
+        .read()
+        .unwrap_or_else(|| sample_input(RaffleMode::Winners, kind));
+    assert_eq!(
+        input.mode,
+        RaffleMode::Winners,
+        "the input does not draw winners"
+    );
+    assert!(
+        input.commitment_tree == kind && input.winners_tree == kind,
+        "the input does not commit OpenZeppelin trees"
+    );
+    let entries = derive_entries(&input.balances, input.weight_policy);
+    let winners = draw(
+        input.num_winners,
+        &input.quotas,
+        input.draw_seed(),
+        &entries,
+    );
+    let entry_addresses: Vec<[u8; 20]> = entries.iter().map(|entry| entry.address).collect();
+
+    let addresses = match args.addresses.is_empty() {
+        true => winners.clone(),
+        false => args.addresses,
+    };
+    let entry_indices: Vec<usize> = addresses
+        .iter()
+        .map(|address| {
+            entry_addresses
+                .iter()
+                .position(|a| a == address)
+                .unwrap_or_else(|| panic!("{} has no entry", to_hex(address)))
+        })
+        .collect();
+    let winner_indices: Vec<usize> = addresses
+        .iter()
+        .filter_map(|address| winners.iter().position(|w| w == address))
+        .collect();
+
+    let commitment_root = get_commitment_root(&entries, kind);
+    let winners_root = get_winners_root(
+        input.num_winners,
+        &input.quotas,
+        input.draw_seed(),
+        &entries,
+        kind,
+    );
+    let output = MultiProofs {
+        commitment: tree_multi_proof(
+            commitment_root,
+            get_commitment_multi_proof(&entries, &entry_indices, kind),
+            &entry_addresses,
+        ),
+        winners: tree_multi_proof(
+            winners_root,
+            get_winners_multi_proof(
+                input.num_winners,
+                &input.quotas,
+                input.draw_seed(),
+                &entries,
+                &winner_indices,
+                kind,
+            ),
+            &winners,
+        ),
+    };
+
+    let json = serde_json::to_string_pretty(&output).unwrap();
+    match args.output {
+        Some(path) => std::fs::write(path, json).expect("failed to write multiproofs"),
+        None => println!("{}", json),
+    }
+}
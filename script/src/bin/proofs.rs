@@ -0,0 +1,137 @@
+//! A script to print the Merkle proof of every entry against the commitment root and of every
+//! winner against the winners root, as committed by the program in winners mode.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package weighted-raffle-script --bin proofs --release -- --output proofs.json
+//! ```
+//!
+//! Pass `--input <json>` to prove the entries and winners of a program input instead of the sample
+//! input.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use serde::Serialize;
+use weighted_raffle_program::{
+    merkle::{
+        get_commitment_root, get_winners_root,
+        proof::{get_commitment_proofs, get_winners_proofs, verify_proof, LeafProof},
+        TreeKind,
+    },
+    raffle::weight::derive_entries,
+    RaffleMode,
+};
+use weighted_raffle_script::{sample_input, to_hex, InputArgs, TreeArgs};
+
+/// The arguments for the proofs command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct ProofsArgs {
+    /// Write the proofs to this file instead of stdout.
+    #[clap(long)]
+    output: Option<PathBuf>,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+
+    #[clap(flatten)]
+    input: InputArgs,
+}
+
+/// The leaf of an address, its index in the list the tree was built from and its sibling path.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddressProof {
+    address: String,
+    index: u64,
+    leaf: String,
+    proof: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TreeProofs {
+    root: String,
+    num_leaves: usize,
+    proofs: Vec<AddressProof>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Proofs {
+    commitment: TreeProofs,
+    winners: TreeProofs,
+}
+
+fn tree_proofs(root: [u8; 32], proofs: Vec<LeafProof>, kind: TreeKind) -> TreeProofs {
+    for proof in &proofs {
+        assert!(
+            verify_proof(&root, proof, proofs.len() as u64, kind),
+            "invalid proof for {}",
+            to_hex(proof.address)
+        );
+    }
+    TreeProofs {
+        root: to_hex(root),
+        num_leaves: proofs.len(),
+        proofs: proofs
+            .iter()
+            .map(|proof| AddressProof {
+                address: to_hex(proof.address),
+                index: proof.index,
+                leaf: to_hex(proof.leaf),
+                proof: proof.proof.iter().map(to_hex).collect(),
+            })
+            .collect(),
+    }
+}
+
+fn main() {
+    let args = ProofsArgs::parse();
+
+    // Rebuild the commitment and winners trees exactly as the program does in winners mode.
+    let input = args
+        .input
+        .read()
+        .unwrap_or_else(|| sample_input(RaffleMode::Winners, args.tree.kind()));
+    assert_eq!(
+        input.mode,
+        RaffleMode::Winners,
+        "the input does not draw winners"
+    );
+    let entries = derive_entries(&input.balances, input.weight_policy);
+
+    let commitment_root = get_commitment_root(&entries, input.commitment_tree);
+    let winners_root = get_winners_root(
+        input.num_winners,
+        &input.quotas,
+        input.draw_seed(),
+        &entries,
+        input.winners_tree,
+    );
+    let output = Proofs {
+        commitment: tree_proofs(
+            commitment_root,
+            get_commitment_proofs(&entries, input.commitment_tree),
+            input.commitment_tree,
+        ),
+        winners: tree_proofs(
+            winners_root,
+            get_winners_proofs(
+                input.num_winners,
+                &input.quotas,
+                input.draw_seed(),
+                &entries,
+                input.winners_tree,
+            ),
+            input.winners_tree,
+        ),
+    };
+
+    let json = serde_json::to_string_pretty(&output).unwrap();
+    match args.output {
+        Some(path) => std::fs::write(path, json).expect("failed to write proofs"),
+        None => println!("{}", json),
+    }
+}
@@ -1,9 +1,11 @@
 use std::path::PathBuf;
 
-use alloy_sol_types::{sol, SolType};
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 use sp1_sdk::{HashableKey, ProverClient, SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey};
+use weighted_raffle_program::{fingerprint::input_hash, merkle::TreeKind, RaffleMode};
+use weighted_raffle_public_values::RafflePublicValues;
+use weighted_raffle_script::{sample_input, to_hex, InputArgs, TreeArgs};
 
 /// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
 ///
@@ -16,27 +18,36 @@ pub const ELF: &[u8] = include_bytes!("../../../program/elf/riscv32im-succinct-z
 struct ProveArgs {
     #[clap(long, default_value = "false")]
     evm: bool,
-}
 
-/// The public values encoded as a tuple that can be easily deserialized inside Solidity.
-type PublicValuesTuple = sol! {
-    // commitment_root, seed, winners_root
-    tuple(bytes32,bytes32,bytes32)
-};
-
-#[derive(Serialize, Deserialize)]
-struct Entry {
-    address: [u8; 20],
-    start: u64,
-    end: u64,
-}
+    /// Rank every entry instead of drawing winners.
+    #[clap(long, default_value = "false", conflicts_with = "input")]
+    ranking: bool,
+
+    /// Draw from the entries hit by the tickets only, proven against the commitment root, instead
+    /// of reading every entry.
+    #[clap(long, default_value = "false", conflicts_with_all = ["ranking", "input"])]
+    sublinear: bool,
+
+    /// Only validate the entries and commit their root, total weight and number of entries.
+    #[clap(long, default_value = "false", conflicts_with_all = ["ranking", "sublinear", "input"])]
+    register: bool,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
 
-// TODO: Import this from a shared project
-#[derive(Serialize, Deserialize)]
-struct WeightedRaffleProgramInput {
-    seed: [u8; 32],
-    entries: Vec<Entry>,
-    num_winners: u64,
+    /// Commit the winners in a sparse Merkle tree keyed by address, which can also prove that an
+    /// address did not win. A ranking cannot be committed in a sparse tree.
+    #[clap(long, default_value = "false", conflicts_with_all = ["ranking", "input"])]
+    sparse_winners: bool,
+
+    // The mode and tree options only build the sample input, so they conflict with `--input`.
+    #[clap(flatten)]
+    input: InputArgs,
+
+    /// Also commit the winners (or the ranking) inline in the public values, failing if there are
+    /// more than this many.
+    #[clap(long, conflicts_with = "input")]
+    inline_winners: Option<u64>,
 }
 
 fn main() {
@@ -55,30 +66,28 @@ fn main() {
     // Setup the inputs.
 
     let mut stdin = SP1Stdin::new();
-    let mut entries: Vec<Entry> = vec![];
-    for i in 0..1000u64 {
-        let address: [u8; 20] = [vec![0u8; 12], i.to_be_bytes().to_vec()]
-            .concat()
-            .as_slice()
-            .try_into()
-            .unwrap();
-        entries.push(Entry {
-            address,
-            start: i * 10,
-            end: i * 10 + 10,
-        });
-    }
-    let mut seed = [0u8; 32];
-    hex::decode_to_slice(
-        "deadbeeffeedfacedeadbeeffeedfacedeadbeeffeedfacedeadbeeffeedface",
-        &mut seed,
-    )
-    .unwrap();
-    let input = WeightedRaffleProgramInput {
-        seed,
-        entries,
-        num_winners: 10,
+    let mode = if args.ranking {
+        RaffleMode::Ranking
+    } else if args.sublinear {
+        RaffleMode::Sublinear
+    } else if args.register {
+        RaffleMode::Register
+    } else {
+        RaffleMode::Winners
     };
+    let input = args.input.read().unwrap_or_else(|| {
+        let mut input = sample_input(mode, args.tree.kind());
+        if args.sparse_winners {
+            input.winners_tree = TreeKind::Sparse(input.winners_tree.encoding());
+        }
+        input.inline_winners = args.inline_winners;
+        input
+    });
+    // Fail before proving on the trees the program rejects.
+    input.check_trees();
+    // The fingerprint the program commits for this input.
+    let input_hash = input_hash(&input);
+    println!("Input hash: {}", to_hex(input_hash));
     stdin.write(&input);
 
     if args.evm {
@@ -88,7 +97,7 @@ fn main() {
             .plonk()
             .run()
             .expect("failed to generate proof");
-        create_plonk_fixture(&proof, &vk);
+        create_plonk_fixture(&proof, &vk, input_hash);
     } else {
         // Generate the proof.
         // let proof = client
@@ -97,12 +106,11 @@ fn main() {
         //     .expect("failed to generate proof");
         // let proof = proof.public_values;
         let (public_values, _) = client.execute(ELF, stdin).run().unwrap();
-        let (commit_root, seed, winners_root) =
-            PublicValuesTuple::abi_decode(public_values.as_slice(), false).unwrap();
+        let values = RafflePublicValues::decode(public_values.as_slice())
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(values.input_hash, input_hash, "unexpected input hash");
         println!("Successfully generated proof!");
-        println!("Commitment root: {:?}", commit_root);
-        println!("Seed: {:?}", seed);
-        println!("Winners root: {:?}", winners_root);
+        println!("{values}");
 
         // Verify the proof.
         // client.verify(&proof, &vk).expect("failed to verify proof");
@@ -113,25 +121,28 @@ fn main() {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct SP1ProofFixture {
-    commit_root: String,
-    seed: String,
-    winners_root: String,
+    // Keeps the keys of the earlier fixtures (`commitRoot`, `seed`, `winnersRoot`, ...)
+    #[serde(flatten)]
+    values: RafflePublicValues,
     vkey: String,
     public_values: String,
     proof: String,
 }
 
 /// Create a fixture for the given proof.
-fn create_plonk_fixture(proof: &SP1ProofWithPublicValues, vk: &SP1VerifyingKey) {
+fn create_plonk_fixture(
+    proof: &SP1ProofWithPublicValues,
+    vk: &SP1VerifyingKey,
+    input_hash: [u8; 32],
+) {
     // Deserialize the public values.
     let bytes = proof.public_values.as_slice();
-    let (commit_root, seed, winners_root) = PublicValuesTuple::abi_decode(bytes, false).unwrap();
+    let values = RafflePublicValues::decode(bytes).unwrap_or_else(|err| panic!("{err}"));
+    assert_eq!(values.input_hash, input_hash, "unexpected input hash");
 
     // Create the testing fixture so we can test things end-ot-end.
     let fixture = SP1ProofFixture {
-        commit_root: commit_root.to_string(),
-        seed: seed.to_string(),
-        winners_root: winners_root.to_string(),
+        values,
         vkey: vk.bytes32().to_string(),
         public_values: format!("0x{}", hex::encode(bytes)),
         proof: format!("0x{}", hex::encode(proof.bytes())),
@@ -0,0 +1,101 @@
+//! A script to print every address's rank in the full weighted ranking, together with the Merkle
+//! proof of its `H(rank || address)` leaf against the ranking root committed by the program.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package weighted-raffle-script --bin rank --release -- --output ranking.json
+//! ```
+//!
+//! Pass `--input <json>` to rank the entries of a program input instead of the sample input.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use serde::Serialize;
+use weighted_raffle_program::{
+    merkle::get_ranking_leaves,
+    raffle::{rank, weight::derive_entries},
+    RaffleMode,
+};
+use weighted_raffle_script::{sample_input, to_hex, InputArgs, TreeArgs};
+
+/// The arguments for the rank command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct RankArgs {
+    /// Write the ranking to this file instead of stdout.
+    #[clap(long)]
+    output: Option<PathBuf>,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+
+    #[clap(flatten)]
+    input: InputArgs,
+}
+
+/// The rank of an address and the proof of its leaf in the ranking tree.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RankProof {
+    address: String,
+    rank: u64,
+    leaf: String,
+    proof: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Ranking {
+    ranking_root: String,
+    num_leaves: usize,
+    ranks: Vec<RankProof>,
+}
+
+fn main() {
+    let args = RankArgs::parse();
+
+    // Rebuild the ranking tree exactly as the program does in ranking mode.
+    let input = args
+        .input
+        .read()
+        .unwrap_or_else(|| sample_input(RaffleMode::Ranking, args.tree.kind()));
+    assert_eq!(
+        input.mode,
+        RaffleMode::Ranking,
+        "the input does not rank the entries"
+    );
+    assert!(
+        input.quotas.is_empty(),
+        "quotas are not supported in rankings"
+    );
+    let entries = derive_entries(&input.balances, input.weight_policy);
+    let ranking = rank(input.draw_seed(), &entries);
+    let leaves = get_ranking_leaves(&ranking, input.winners_tree);
+    let proofs = input
+        .winners_tree
+        .proofs(&leaves)
+        .expect("sparse trees can only commit the winners, not the ranking");
+
+    let output = Ranking {
+        ranking_root: to_hex(input.winners_tree.root(&leaves).unwrap()),
+        num_leaves: leaves.len(),
+        ranks: ranking
+            .iter()
+            .zip(leaves.iter().zip(proofs))
+            .enumerate()
+            .map(|(rank, (address, (leaf, proof)))| RankProof {
+                address: to_hex(address),
+                rank: rank as u64,
+                leaf: to_hex(leaf),
+                proof: proof.iter().map(to_hex).collect(),
+            })
+            .collect(),
+    };
+
+    let json = serde_json::to_string_pretty(&output).unwrap();
+    match args.output {
+        Some(path) => std::fs::write(path, json).expect("failed to write ranking"),
+        None => println!("{}", json),
+    }
+}
@@ -0,0 +1,160 @@
+//! A script to prove the sample entries, or the entries of an input, in shards, in parallel, and
+//! aggregate the shard proofs recursively into a single proof of the commitment root, total weight and number of entries.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! RUST_LOG=info cargo run --package weighted-raffle-script --bin shards --release -- \
+//!     --shard-size 256
+//! ```
+
+use alloy_sol_types::SolType;
+use clap::Parser;
+use sp1_sdk::{HashableKey, ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1Stdin};
+use weighted_raffle_program::{
+    merkle::register_entries,
+    raffle::weight::derive_entries,
+    shard::{
+        shard_vkey_bytes, AggregationProgramInput, AggregationPublicValuesTuple, ShardProgramInput,
+    },
+    RaffleMode,
+};
+use weighted_raffle_script::{sample_input, to_hex, InputArgs, TreeArgs};
+
+/// The ELF of the shard program, which validates a shard of entries and commits its summary.
+///
+/// This file is generated by running `cargo prove build` inside the `shard-program` directory.
+pub const SHARD_ELF: &[u8] =
+    include_bytes!("../../../shard-program/elf/riscv32im-succinct-zkvm-elf");
+
+/// The ELF of the aggregation program, which verifies the shard proofs and combines them.
+///
+/// This file is generated by running `cargo prove build` inside the `aggregation-program`
+/// directory.
+pub const AGGREGATION_ELF: &[u8] =
+    include_bytes!("../../../aggregation-program/elf/riscv32im-succinct-zkvm-elf");
+
+/// The arguments for the shards command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct ShardsArgs {
+    /// The number of entries of every shard but the last, a power of two.
+    #[clap(long, default_value = "256")]
+    shard_size: u64,
+
+    /// Only positional, tagged, poseidon and sha256 trees can be sharded.
+    #[clap(flatten)]
+    tree: TreeArgs,
+
+    #[clap(flatten)]
+    input: InputArgs,
+}
+
+fn main() {
+    // Setup the logger.
+    sp1_sdk::utils::setup_logger();
+
+    // Parse the command line arguments.
+    let args = ShardsArgs::parse();
+    assert!(
+        args.shard_size.is_power_of_two(),
+        "the shard size must be a power of two"
+    );
+    let input = args
+        .input
+        .read()
+        .unwrap_or_else(|| sample_input(RaffleMode::Register, args.tree.kind()));
+    let kind = input.commitment_tree;
+    let entries = derive_entries(&input.balances, input.weight_policy);
+
+    // Setup the prover client and the programs.
+    let client = ProverClient::new();
+    let (shard_pk, shard_vk) = client.setup(SHARD_ELF);
+    let (aggregation_pk, aggregation_vk) = client.setup(AGGREGATION_ELF);
+
+    // Prove the shards in parallel, as compressed proofs that can be verified in the zkVM.
+    let shard_proofs: Vec<SP1ProofWithPublicValues> = std::thread::scope(|scope| {
+        let handles: Vec<_> = entries
+            .chunks(args.shard_size as usize)
+            .enumerate()
+            .map(|(i, shard)| {
+                let (client, shard_pk) = (&client, &shard_pk);
+                let input = ShardProgramInput {
+                    first_index: i as u64 * args.shard_size,
+                    entries: shard.to_vec(),
+                    commitment_tree: kind,
+                };
+                scope.spawn(move || {
+                    let mut stdin = SP1Stdin::new();
+                    stdin.write(&input);
+                    client
+                        .prove(shard_pk, stdin)
+                        .compressed()
+                        .run()
+                        .expect("failed to generate shard proof")
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    println!("Proved {} shards", shard_proofs.len());
+
+    // Aggregate the shards: the program reads their public values, and the proofs are verified
+    // in the same order.
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&AggregationProgramInput {
+        shard_vkey: shard_vk.hash_u32(),
+        shards: shard_proofs
+            .iter()
+            .map(|proof| proof.public_values.to_vec())
+            .collect(),
+        commitment_tree: kind,
+    });
+    for proof in shard_proofs {
+        let SP1Proof::Compressed(proof) = proof.proof else {
+            panic!("expected a compressed shard proof");
+        };
+        stdin.write_proof(proof, shard_vk.vk.clone());
+    }
+    let proof = client
+        .prove(&aggregation_pk, stdin)
+        .compressed()
+        .run()
+        .expect("failed to generate aggregation proof");
+    client
+        .verify(&proof, &aggregation_vk)
+        .expect("failed to verify proof");
+
+    let (
+        commitment_root,
+        total_weight,
+        entry_count,
+        commitment_tree,
+        leaf_encoding,
+        shard_vkey,
+        shard_count,
+    ) = AggregationPublicValuesTuple::abi_decode(proof.public_values.as_slice(), false).unwrap();
+    println!("Successfully generated proof!");
+    println!("Commitment root: {}", to_hex(commitment_root));
+    println!("Total weight: {} ({} entries)", total_weight, entry_count);
+    println!(
+        "Tree kind: {} (leaf encoding: {})",
+        commitment_tree, leaf_encoding
+    );
+    // The committed vkey is the shard program's BabyBear digest packed big-endian, which differs
+    // from the BN254 digest of `bytes32()`.
+    assert_eq!(shard_vkey.0, shard_vkey_bytes(&shard_vk.hash_u32()));
+    println!("Shards: {}", shard_count);
+    println!("Shard vkey (committed, hash_u32): {}", to_hex(shard_vkey));
+    println!("Shard vkey (bytes32): {}", shard_vk.bytes32());
+
+    // The combined root is the one a single register proof would commit.
+    let registration = register_entries(&entries, kind);
+    assert_eq!(commitment_root.0, registration.commitment_root);
+    assert_eq!(
+        (total_weight, entry_count),
+        (registration.total_weight, registration.entry_count)
+    );
+}
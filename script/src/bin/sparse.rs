@@ -0,0 +1,135 @@
+//! A script to print the membership proof of winners, or the non-membership proof of any other
+//! address, against the root of the winners committed in a sparse Merkle tree.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package weighted-raffle-script --bin sparse --release -- \
+//!     --address 0x00000000000000000000000000000000000003e7
+//! ```
+//!
+//! Pass `--input <json>` to prove against the winners of a program input, which must commit them in
+//! a sparse tree, instead of the sample input.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use serde::Serialize;
+use weighted_raffle_program::{
+    merkle::{get_winners_root, proof::get_sparse_winners_proof, sparse::verify, TreeKind},
+    raffle::{draw, weight::derive_entries},
+    RaffleMode,
+};
+use weighted_raffle_script::{parse_address, sample_input, to_hex, InputArgs, TreeArgs};
+
+/// The arguments for the sparse command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct SparseArgs {
+    /// An address to prove (non-)membership of, repeated. Defaults to every winner.
+    #[clap(long = "address", value_parser = parse_address)]
+    addresses: Vec<[u8; 20]>,
+
+    /// Write the proofs to this file instead of stdout.
+    #[clap(long)]
+    output: Option<PathBuf>,
+
+    /// Only the leaf encoding applies to the sparse winners tree.
+    #[clap(flatten)]
+    tree: TreeArgs,
+
+    #[clap(flatten)]
+    input: InputArgs,
+}
+
+/// The (non-)membership proof of an address, with siblings compressed by the bitmap.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddressProof {
+    address: String,
+    key: String,
+    member: bool,
+    leaf: String,
+    bitmap: String,
+    siblings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SparseProofs {
+    winners_root: String,
+    proofs: Vec<AddressProof>,
+}
+
+fn main() {
+    let args = SparseArgs::parse();
+
+    // Rebuild the sparse winners tree exactly as the program does in winners mode.
+    let input = args.input.read().unwrap_or_else(|| {
+        let mut input = sample_input(RaffleMode::Winners, args.tree.kind());
+        input.winners_tree = TreeKind::Sparse(input.winners_tree.encoding());
+        input
+    });
+    assert_eq!(
+        input.mode,
+        RaffleMode::Winners,
+        "the input does not draw winners"
+    );
+    let TreeKind::Sparse(encoding) = input.winners_tree else {
+        panic!("the input does not commit its winners in a sparse tree");
+    };
+    let entries = derive_entries(&input.balances, input.weight_policy);
+    let root = get_winners_root(
+        input.num_winners,
+        &input.quotas,
+        input.draw_seed(),
+        &entries,
+        input.winners_tree,
+    );
+
+    let addresses = match args.addresses.is_empty() {
+        true => draw(
+            input.num_winners,
+            &input.quotas,
+            input.draw_seed(),
+            &entries,
+        ),
+        false => args.addresses,
+    };
+    let proofs = addresses
+        .iter()
+        .map(|address| {
+            let proof = get_sparse_winners_proof(
+                address,
+                input.num_winners,
+                &input.quotas,
+                input.draw_seed(),
+                &entries,
+                encoding,
+            );
+            assert!(
+                verify(&root, &proof),
+                "invalid proof for {}",
+                to_hex(address)
+            );
+            AddressProof {
+                address: to_hex(address),
+                key: to_hex(proof.key),
+                member: proof.is_member(),
+                leaf: to_hex(proof.leaf),
+                bitmap: to_hex(proof.bitmap),
+                siblings: proof.siblings.iter().map(to_hex).collect(),
+            }
+        })
+        .collect();
+
+    let output = SparseProofs {
+        winners_root: to_hex(root),
+        proofs,
+    };
+
+    let json = serde_json::to_string_pretty(&output).unwrap();
+    match args.output {
+        Some(path) => std::fs::write(path, json).expect("failed to write proofs"),
+        None => println!("{}", json),
+    }
+}
@@ -0,0 +1,208 @@
+//! Helpers shared by the script binaries.
+use std::path::{Path, PathBuf};
+
+use clap::{Args, ValueEnum};
+use weighted_raffle_program::{
+    merkle::{leaf::LeafEncoding, proof::get_sublinear_draw, TreeKind},
+    raffle::weight::{derive_entries, Balance, WeightPolicy},
+    RaffleMode, WeightedRaffleProgramInput,
+};
+
+/// The input the binaries run the raffle on: 1000 entries of equal balance, in raffle 1 of a
+/// contract on a local anvil chain.
+pub fn sample_input(mode: RaffleMode, tree: TreeKind) -> WeightedRaffleProgramInput {
+    sample_input_of_size(1000, mode, tree)
+}
+
+/// The sample input with `num_entries` entries of equal balance.
+///
+/// In sublinear mode, the balances are replaced by the entries hit by the draw and their proofs.
+pub fn sample_input_of_size(
+    num_entries: u64,
+    mode: RaffleMode,
+    tree: TreeKind,
+) -> WeightedRaffleProgramInput {
+    let mut balances: Vec<Balance> = vec![];
+    for i in 0..num_entries {
+        let address: [u8; 20] = [vec![0u8; 12], i.to_be_bytes().to_vec()]
+            .concat()
+            .as_slice()
+            .try_into()
+            .unwrap();
+        balances.push(Balance {
+            address,
+            balance: 10,
+            group: None,
+        });
+    }
+    let mut seed = [0u8; 32];
+    hex::decode_to_slice(
+        "deadbeeffeedfacedeadbeeffeedfacedeadbeeffeedfacedeadbeeffeedface",
+        &mut seed,
+    )
+    .unwrap();
+    // First contract deployed on a local anvil node
+    let raffle_contract = parse_address("0x5fbdb2315678afecb367f032d93f642f64180aa3").unwrap();
+    let mut input = WeightedRaffleProgramInput {
+        seed,
+        balances,
+        weight_policy: WeightPolicy::Linear,
+        mode,
+        num_winners: 10,
+        quotas: vec![],
+        commitment_tree: tree,
+        winners_tree: tree,
+        sublinear: None,
+        raffle_id: 1,
+        chain_id: 31337,
+        raffle_contract,
+        inline_winners: None,
+    };
+    if mode == RaffleMode::Sublinear {
+        let entries = derive_entries(&input.balances, input.weight_policy);
+        input.balances.clear();
+        input.sublinear = Some(get_sublinear_draw(
+            input.num_winners,
+            input.draw_seed(),
+            &entries,
+            tree,
+        ));
+    }
+    input
+}
+
+/// The program input selected on the command line.
+#[derive(Args, Debug, Clone)]
+pub struct InputArgs {
+    /// Read the program input from a JSON file instead of using the sample input.
+    #[clap(long)]
+    input: Option<PathBuf>,
+}
+
+impl InputArgs {
+    /// The input read from the `--input` file, if one was given.
+    pub fn read(&self) -> Option<WeightedRaffleProgramInput> {
+        self.input.as_deref().map(read_input)
+    }
+}
+
+/// Read a program input from a JSON file.
+pub fn read_input(path: &Path) -> WeightedRaffleProgramInput {
+    let json = std::fs::read(path).expect("failed to read input file");
+    serde_json::from_slice(&json).expect("failed to parse input file")
+}
+
+/// Format bytes as a 0x-prefixed hex string.
+pub fn to_hex(bytes: impl AsRef<[u8]>) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Parse a 0x-prefixed address.
+pub fn parse_address(address: &str) -> Result<[u8; 20], String> {
+    let mut out = [0u8; 20];
+    hex::decode_to_slice(address.trim_start_matches("0x"), &mut out)
+        .map_err(|err| format!("invalid address: {err}"))?;
+    Ok(out)
+}
+
+/// Parse a prize tier given as `COUNT:AMOUNT`, e.g. `3:500` for three prizes of 500.
+pub fn parse_tier(tier: &str) -> Result<(u64, u128), String> {
+    let (count, amount) = tier
+        .split_once(':')
+        .ok_or_else(|| format!("expected COUNT:AMOUNT, got {tier}"))?;
+    let count = count
+        .parse()
+        .map_err(|err| format!("invalid count: {err}"))?;
+    let amount = amount
+        .parse()
+        .map_err(|err| format!("invalid amount: {err}"))?;
+    Ok((count, amount))
+}
+
+/// The amount of each prize in draw order, the first tier going to the first winners drawn.
+pub fn prize_amounts(tiers: &[(u64, u128)], num_winners: u64) -> Vec<u128> {
+    let amounts: Vec<u128> = tiers
+        .iter()
+        .flat_map(|(count, amount)| std::iter::repeat(*amount).take(*count as usize))
+        .collect();
+    assert!(
+        amounts.len() as u64 == num_winners,
+        "tiers hold {} prizes but {} winners are drawn",
+        amounts.len(),
+        num_winners
+    );
+    amounts
+}
+
+/// The Merkle tree layout and leaf encoding of the sample input selected on the command line.
+///
+/// They conflict with `--input` (see `InputArgs`), as an input names its own.
+#[derive(Args, Debug, Clone, Copy)]
+pub struct TreeArgs {
+    /// The Merkle tree layout.
+    #[clap(
+        long,
+        value_enum,
+        default_value = "positional",
+        conflicts_with = "input"
+    )]
+    tree: TreeArg,
+
+    /// The encoding of the leaf values. Defaults to packed, except for OpenZeppelin and Poseidon
+    /// trees which always abi-encode their leaves.
+    #[clap(long, value_enum, conflicts_with = "input")]
+    leaf_encoding: Option<EncodingArg>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum TreeArg {
+    /// rs_merkle tree, as committed by earlier versions.
+    Positional,
+    /// OpenZeppelin StandardMerkleTree.
+    Openzeppelin,
+    /// Positional tree with domain-separated leaves and nodes, committing to its leaf count.
+    Tagged,
+    /// Depth-32 append-only tree padded with zero hashes, as kept by on-chain registries.
+    Incremental,
+    /// Positional tree hashed with Poseidon over BN254, as in circomlib.
+    Poseidon,
+    /// Positional tree hashed with SHA-256.
+    Sha256,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum EncodingArg {
+    /// abi.encodePacked(values), with the values' own widths.
+    Packed,
+    /// abi.encode(values), with integers widened to uint256.
+    Abi,
+}
+
+impl TreeArgs {
+    pub fn kind(&self) -> TreeKind {
+        let encoding = match self.leaf_encoding {
+            None | Some(EncodingArg::Packed) => LeafEncoding::Packed,
+            Some(EncodingArg::Abi) => LeafEncoding::Abi,
+        };
+        match self.tree {
+            TreeArg::Positional => TreeKind::Positional(encoding),
+            TreeArg::Openzeppelin => {
+                assert!(
+                    !matches!(self.leaf_encoding, Some(EncodingArg::Packed)),
+                    "OpenZeppelin trees always abi-encode their leaves"
+                );
+                TreeKind::OpenZeppelin
+            }
+            TreeArg::Tagged => TreeKind::Tagged(encoding),
+            TreeArg::Incremental => TreeKind::Incremental(encoding),
+            TreeArg::Poseidon => {
+                assert!(
+                    !matches!(self.leaf_encoding, Some(EncodingArg::Packed)),
+                    "Poseidon trees always abi-encode their leaves"
+                );
+                TreeKind::Poseidon
+            }
+            TreeArg::Sha256 => TreeKind::Sha256(encoding),
+        }
+    }
+}
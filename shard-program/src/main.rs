@@ -0,0 +1,14 @@
+#![cfg_attr(not(test), no_main)]
+#[cfg(not(test))]
+sp1_zkvm::entrypoint!(main);
+
+use weighted_raffle_program::shard::{summarize_shard, ShardProgramInput};
+
+pub fn main() {
+    let input = sp1_zkvm::io::read::<ShardProgramInput>();
+
+    let summary = summarize_shard(input.first_index, &input.entries, input.commitment_tree);
+
+    // Commit to the public values of the program.
+    sp1_zkvm::io::commit_slice(&summary.abi_encode());
+}